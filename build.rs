@@ -25,8 +25,7 @@ fn replace_res_dir(){
 fn get_output_path() -> PathBuf {
     let manifest_dir_string = env::var("CARGO_MANIFEST_DIR").unwrap();
     let build_type = env::var("PROFILE").unwrap();
-    let path = Path::new(&manifest_dir_string).join("target").join(build_type);
-    return PathBuf::from(path);
+    Path::new(&manifest_dir_string).join("target").join(build_type)
 }
 
 fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()> {
@@ -0,0 +1,98 @@
+use std::error::Error;
+use std::fs;
+use std::ptr;
+use chrono::{DateTime, Local};
+use rust_decimal::Decimal;
+use rust_xlsxwriter::Workbook;
+use sha2::{Digest, Sha256};
+use crate::init_data::InitialData;
+use crate::orders_plan::find_specification;
+use crate::settings::Settings;
+use crate::xls_matrix::{XlsCell, XlsMatrix, XlsSheetOption};
+use crate::xlsx::input_files;
+
+const DATE_FORMAT: &str = "dd.mm.yyyy";
+
+/// Таблица для листа проверки: строка заголовков, автофильтр, защита от изменений
+fn audit_matrix(headers: &[&str], rows: Vec<Vec<XlsCell>>) -> XlsMatrix {
+    let mut matrix = XlsMatrix::table(headers, rows, 0);
+    matrix.sheet_options.push(XlsSheetOption::Protect);
+    matrix
+}
+
+fn files_matrix(settings: &Settings) -> Result<XlsMatrix, Box<dyn Error>> {
+    let mut rows = vec![];
+    for path in input_files(settings) {
+        let metadata = fs::metadata(&path)?;
+        let modified: DateTime<Local> = metadata.modified()?.into();
+        let hash = Sha256::digest(fs::read(&path)?);
+        rows.push(vec![
+            XlsCell::text(&path),
+            XlsCell::text(&modified.format("%d.%m.%Y %H:%M:%S").to_string()),
+            XlsCell::decimal(Decimal::from(metadata.len())),
+            XlsCell::text(&hash.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+        ]);
+    }
+    Ok(audit_matrix(&["Файл", "Изменен", "Размер, байт", "SHA-256"], rows))
+}
+
+fn specifications_matrix(init_data: &InitialData) -> XlsMatrix {
+    let mut rows = vec![];
+    for sp in init_data.specifications.iter() {
+        let in_plan = init_data.purchase_plan_items.iter().any(|ppi| ppi.product_name == sp.product_name);
+        // применяется первая найденная версия спецификации товара
+        let applied = if !in_plan {
+            "нет в плане"
+        } else if find_specification(sp.product_name.clone(), &init_data.specifications).is_ok_and(|found| ptr::eq(found, sp)) {
+            "да"
+        } else {
+            "нет"
+        };
+        for spi in sp.items.iter() {
+            rows.push(vec![XlsCell::text(&sp.product_name), XlsCell::date(sp.date_from, DATE_FORMAT), XlsCell::text(applied),
+                           XlsCell::text(&spi.material_name), XlsCell::decimal(spi.qty)]);
+        }
+    }
+    audit_matrix(&["Товар", "Действует с", "Применена", "Материал", "Количество"], rows)
+}
+
+/// Листы "Исх. ..." с исходными данными в том виде, в котором они прочитаны для расчета,
+/// и лист "Исх. файлы" с путями, временем изменения и хешами файлов исходных данных
+pub fn write_audit_sheets(workbook: &mut Workbook, init_data: &InitialData, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let plan = init_data.purchase_plan_items.iter()
+        .map(|ppi| vec![XlsCell::text(&ppi.product_name), XlsCell::date(ppi.date, DATE_FORMAT), XlsCell::decimal(ppi.qty)])
+        .collect();
+    let stocks = init_data.stocks.iter()
+        .map(|mi| vec![XlsCell::text(&mi.material), XlsCell::date(mi.date, DATE_FORMAT), XlsCell::decimal(mi.qty)])
+        .collect();
+    let orders = init_data.purchase_orders.iter()
+        .flat_map(|po| po.items.iter().map(|mi| vec![XlsCell::text(&po.name), XlsCell::text(po.status.name()), XlsCell::text(&mi.material), XlsCell::date(mi.date, DATE_FORMAT), XlsCell::decimal(mi.qty)]))
+        .collect();
+    let delivery_times = init_data.delivery_times.iter()
+        .map(|dt| vec![
+            XlsCell::text(&dt.material_name),
+            XlsCell::decimal(Decimal::from(dt.qty)),
+            XlsCell::text(dt.unit.name()),
+            XlsCell::text(dt.supplier.as_deref().unwrap_or_default()),
+            match dt.safety_stock {
+                Some(qty) => XlsCell::decimal(qty),
+                None => XlsCell::text("")
+            },
+        ])
+        .collect();
+
+    let sheets = [
+        ("Исх. файлы", files_matrix(settings)?),
+        ("Исх. план", audit_matrix(&["Товар", "Дата", "Количество"], plan)),
+        ("Исх. остатки", audit_matrix(&["Материал", "Дата", "Количество"], stocks)),
+        ("Исх. заказы", audit_matrix(&["Заказ", "Статус", "Материал", "Дата", "Открытое количество"], orders)),
+        ("Исх. сроки", audit_matrix(&["Материал", "Срок", "Единица", "Поставщик", "Страховой запас"], delivery_times)),
+        ("Исх. спецификации", specifications_matrix(init_data)),
+    ];
+    for (name, matrix) in sheets {
+        let sheet = workbook.add_worksheet().set_name(name)?;
+        matrix.write_to_worksheet(sheet);
+        sheet.autofit();
+    }
+    Ok(())
+}
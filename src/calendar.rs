@@ -0,0 +1,55 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+
+/// Нерабочие периоды компании (supplier = None) или отдельного поставщика
+#[derive(Debug)]
+pub struct Calendar {
+    pub(crate) supplier: Option<String>,
+    pub(crate) holidays: Vec<(NaiveDate, NaiveDate)>
+}
+
+impl Calendar {
+
+    pub fn is_holiday(&self, date: &NaiveDate) -> bool {
+        self.holidays.iter().any(|(from, to)| from <= date && date <= to)
+    }
+}
+
+/// Единица измерения срока доставки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeadTimeUnit {
+    Weeks,
+    Days,
+    WorkingDays
+}
+
+impl LeadTimeUnit {
+
+    pub fn parse(value: &str) -> Result<LeadTimeUnit, String> {
+        let normalized: String = value.to_lowercase().chars().filter(|c| c.is_alphabetic()).collect();
+        match normalized.as_str() {
+            "" | "нед" | "недель" | "недели" | "неделя" => Ok(LeadTimeUnit::Weeks),
+            "дн" | "дней" | "дня" | "день" | "кд" => Ok(LeadTimeUnit::Days),
+            "рабдн" | "рабдней" | "рабочихдней" | "рд" => Ok(LeadTimeUnit::WorkingDays),
+            _ => Err(format!("Неизвестная единица срока доставки '{}' (допустимо: нед, дн, раб. дн)", value))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LeadTimeUnit::Weeks => "weeks",
+            LeadTimeUnit::Days => "days",
+            LeadTimeUnit::WorkingDays => "working_days"
+        }
+    }
+
+    /// Считается ли день при отсчете срока доставки
+    pub fn counts(&self, date: &NaiveDate, calendars: &[&Calendar]) -> bool {
+        if calendars.iter().any(|c| c.is_holiday(date)) {
+            return false;
+        }
+        match self {
+            LeadTimeUnit::WorkingDays => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+            _ => true
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::error::Error;
+use chrono::Utc;
+use rust_decimal::prelude::ToPrimitive;
+use rust_xlsxwriter::{Chart, ChartLine, ChartLineDashType, ChartSolidFill, ChartType, Color, Format, RowNum, Workbook};
+use crate::init_data::InitialData;
+use crate::projection::Projection;
+use crate::report::get_horizon;
+use crate::settings::Settings;
+
+const SHEET_NAME: &str = "Графики";
+const HEADERS: [&str; 5] = ["Дата", "Остаток", "Ноль", "Страховой запас", "Горизонт"];
+/// Высота блока материала в строках: не меньше высоты графика
+const MIN_BLOCK_ROWS: usize = 18;
+
+/// Материалы для графиков: выбранные в -charts=, иначе все, у которых есть дефицит
+fn selected_materials(projection: &Projection, settings: &Settings) -> Vec<String> {
+    match &settings.charts {
+        Some(materials) if !materials.is_empty() => {
+            materials.iter()
+                .filter(|m| {
+                    let found = projection.materials.contains(m);
+                    if !found {
+                        println!("Материал '{}' не найден в прогнозе, график не построен", m);
+                    }
+                    found
+                })
+                .cloned()
+                .collect()
+        }
+        _ => projection.materials.iter().filter(|m| projection.first_shortage(m).is_some()).cloned().collect()
+    }
+}
+
+/// Лист "Графики": по блоку на материал - таблица прогноза накопленного остатка и график рядом с ней.
+/// На графике линия нуля, страховой запас (если задан в сроках доставки)
+/// и вертикальная отметка начала интервала за горизонтом срока доставки.
+pub fn write_charts(workbook: &mut Workbook, projection: &Projection, init_data: &InitialData, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let materials = selected_materials(projection, settings);
+    if materials.is_empty() {
+        return Ok(());
+    }
+    let today = Utc::now().naive_utc().date();
+    let dates = &projection.dates;
+    let bold = Format::new().set_bold();
+    let date_format = Format::new().set_num_format(settings.period.num_format());
+
+    let sheet = workbook.add_worksheet().set_name(SHEET_NAME)?;
+    let mut first_row: usize = 0;
+    for material in materials.iter() {
+        let row_num = projection.materials.iter().position(|m| m == material).unwrap();
+        let horizon = get_horizon(init_data, material, &today, settings)?;
+        let safety_stock = init_data.get_delivery_time(material)?.safety_stock;
+
+        sheet.write_with_format(first_row as RowNum, 0, material, &bold)?;
+        for (col, header) in HEADERS.iter().enumerate() {
+            sheet.write_with_format((first_row + 1) as RowNum, col as u16, *header, &bold)?;
+        }
+        // даты прогноза - только интервалы с движениями, отметка ставится на первую дату не раньше горизонта
+        let horizon_index = dates.iter().position(|d| *d >= horizon);
+        for (i, date) in dates.iter().enumerate() {
+            let row = (first_row + 2 + i) as RowNum;
+            sheet.write_with_format(row, 0, date, &date_format)?;
+            sheet.write(row, 1, projection.balances[row_num][i].to_f64())?;
+            sheet.write(row, 2, 0)?;
+            if let Some(safety_stock) = safety_stock {
+                sheet.write(row, 3, safety_stock.to_f64())?;
+            }
+            if horizon_index == Some(i) {
+                sheet.write(row, 4, 1)?;
+            }
+        }
+
+        let data_first = (first_row + 2) as RowNum;
+        let data_last = (first_row + 1 + dates.len().max(1)) as RowNum;
+        let series_range = |col: u16| (SHEET_NAME, data_first, col, data_last, col);
+        let name_cell = |col: u16| (SHEET_NAME, (first_row + 1) as RowNum, col);
+
+        let mut chart = Chart::new(ChartType::Line);
+        chart.title().set_name(material);
+        chart.add_series()
+            .set_name(name_cell(1))
+            .set_categories(series_range(0))
+            .set_values(series_range(1))
+            .set_format(ChartLine::new().set_color(Color::RGB(0x2F5597)).set_width(2.25));
+        chart.add_series()
+            .set_name(name_cell(2))
+            .set_categories(series_range(0))
+            .set_values(series_range(2))
+            .set_format(ChartLine::new().set_color(Color::Black).set_width(0.75));
+        if safety_stock.is_some() {
+            chart.add_series()
+                .set_name(name_cell(3))
+                .set_categories(series_range(0))
+                .set_values(series_range(3))
+                .set_format(ChartLine::new().set_color(Color::Orange).set_dash_type(ChartLineDashType::Dash));
+        }
+        chart.x_axis().set_num_format(settings.period.num_format());
+
+        // отметка горизонта - столбец высотой во всю область графика на скрытой вспомогательной оси
+        let mut horizon_chart = Chart::new(ChartType::Column);
+        horizon_chart.add_series()
+            .set_name(name_cell(4))
+            .set_categories(series_range(0))
+            .set_values(series_range(4))
+            .set_secondary_axis(true)
+            .set_gap(500)
+            .set_format(ChartSolidFill::new().set_color(Color::RGB(0xFF0000)));
+        chart.combine(&horizon_chart);
+        chart.y2_axis().set_min(0).set_max(1).set_hidden(true);
+
+        sheet.insert_chart(first_row as RowNum, HEADERS.len() as u16 + 1, &chart)?;
+        first_row += (dates.len() + 3).max(MIN_BLOCK_ROWS);
+    }
+    sheet.autofit();
+    Ok(())
+}
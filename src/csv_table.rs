@@ -0,0 +1,125 @@
+use std::fs;
+use calamine::{Data, Range};
+use chrono::NaiveDate;
+use encoding_rs::WINDOWS_1251;
+
+/// Текст файла в UTF-8 (с BOM или без) или Windows-1251
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => WINDOWS_1251.decode(bytes).0.into_owned()
+    }
+}
+
+/// Число из текста ячейки: цифры со знаком, десятичная запятая или точка, разделители разрядов - пробелы
+/// или второй из знаков ".", "," ("1.234,5", "1 234,5"). Текст с ведущим нулем ("00123" - код материала),
+/// "nan", "inf" и т.п. числом не считается
+fn parse_number(value: &str) -> Option<f64> {
+    let number: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let digits = number.strip_prefix(['-', '+']).unwrap_or(&number);
+    if !digits.chars().any(|c| c.is_ascii_digit()) || !digits.chars().all(|c| c.is_ascii_digit() || c == '.' || c == ',') {
+        return None;
+    }
+    let separators: Vec<char> = digits.chars().filter(|c| !c.is_ascii_digit()).collect();
+    let decimal = match separators.last() {
+        // один вид разделителя несколько раз - разделитель разрядов
+        Some(last) if separators.iter().all(|c| c == last) && separators.len() > 1 => None,
+        last => last.copied()
+    };
+    if separators.iter().any(|c| Some(*c) != decimal) && separators.iter().filter(|c| Some(**c) == decimal).count() > 1 {
+        return None;
+    }
+    let (integer, fraction) = match decimal {
+        Some(decimal) => digits.rsplit_once(decimal).unwrap(),
+        None => (digits, "")
+    };
+    let integer: String = integer.chars().filter(|c| c.is_ascii_digit()).collect();
+    if (integer.len() > 1 && integer.starts_with('0')) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let sign = if number.starts_with('-') { "-" } else { "" };
+    format!("{}{}.{}", sign, if integer.is_empty() { "0" } else { &integer }, fraction).trim_end_matches('.')
+        .parse::<f64>().ok().filter(|f| f.is_finite())
+}
+
+/// Значение ячейки: число (см. parse_number), дата в формате дд.мм.гггг или гггг-мм-дд, иначе строка
+fn parse_cell(value: &str) -> Data {
+    let value = value.trim();
+    if value.is_empty() {
+        return Data::Empty;
+    }
+    for format in ["%d.%m.%Y", "%Y-%m-%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Data::DateTimeIso(date.format("%Y-%m-%d").to_string());
+        }
+    }
+    match parse_number(value) {
+        Some(f) => Data::Float(f),
+        None => Data::String(value.to_string())
+    }
+}
+
+/// Чтение CSV (разделитель ";") в тот же вид, что и лист xlsx, первая ячейка файла - (0, 0)
+pub fn read_csv_range(path: &str) -> Range<Data> {
+    let text = decode(&fs::read(path).unwrap_or_else(|e| panic!("Не удалось прочитать файл {}: {}", path, e)));
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut rows: Vec<Vec<Data>> = vec![];
+    for record in reader.records() {
+        let record = record.unwrap_or_else(|e| panic!("Ошибка чтения файла {}: {}", path, e));
+        rows.push(record.iter().map(parse_cell).collect());
+    }
+
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    if rows.is_empty() || width == 0 {
+        return Range::empty();
+    }
+    let mut range = Range::new((0, 0), ((rows.len() - 1) as u32, (width - 1) as u32));
+    for (row_num, row) in rows.into_iter().enumerate() {
+        for (col_num, value) in row.into_iter().enumerate() {
+            range.set_value((row_num as u32, col_num as u32), value);
+        }
+    }
+    range
+}
+
+#[cfg(test)]
+mod tests {
+    use calamine::Data;
+    use super::parse_cell;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse_cell("12,5"), Data::Float(12.5));
+        assert_eq!(parse_cell("12.5"), Data::Float(12.5));
+        assert_eq!(parse_cell("-3"), Data::Float(-3.0));
+        assert_eq!(parse_cell("0,25"), Data::Float(0.25));
+        assert_eq!(parse_cell("0"), Data::Float(0.0));
+        assert_eq!(parse_cell("1 234,5"), Data::Float(1234.5));
+        assert_eq!(parse_cell("1.234,5"), Data::Float(1234.5));
+        assert_eq!(parse_cell("1,234.5"), Data::Float(1234.5));
+        assert_eq!(parse_cell("1.234.567"), Data::Float(1234567.0));
+    }
+
+    #[test]
+    fn text_that_looks_numeric() {
+        assert_eq!(parse_cell("00123"), Data::String("00123".to_string()));
+        assert_eq!(parse_cell("nan"), Data::String("nan".to_string()));
+        assert_eq!(parse_cell("inf"), Data::String("inf".to_string()));
+        assert_eq!(parse_cell("-infinity"), Data::String("-infinity".to_string()));
+        assert_eq!(parse_cell("1e5"), Data::String("1e5".to_string()));
+        assert_eq!(parse_cell("1.2.3,4,5"), Data::String("1.2.3,4,5".to_string()));
+        assert_eq!(parse_cell("Резистор 910 0,25W"), Data::String("Резистор 910 0,25W".to_string()));
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_cell("01.04.2024"), Data::DateTimeIso("2024-04-01".to_string()));
+        assert_eq!(parse_cell("2024-04-01"), Data::DateTimeIso("2024-04-01".to_string()));
+    }
+}
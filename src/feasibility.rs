@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::Zero;
+use rust_xlsxwriter::{Color, Worksheet};
+use crate::init_data::{InitialData, PurchasePlanItem};
+use crate::orders_plan::find_specification;
+use crate::settings::Settings;
+use crate::stock_count::calculate_stock_counts;
+use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsMatrix};
+
+/// Выполнимость строки плана обеспечения: сколько товара можно собрать из материалов,
+/// которые к дате строки есть в остатках и поступлениях и не заняты более ранними строками плана
+pub struct Feasibility {
+    /// Приоритет товара при распределении, 1 - высший
+    pub priority: Option<u32>,
+    pub product_name: String,
+    pub date: NaiveDate,
+    pub planned: Decimal,
+    pub buildable: Decimal,
+    /// Материал, которого не хватает в первую очередь, если строку нельзя выполнить полностью
+    pub limiting_material: Option<String>
+}
+
+/// Обеспечение строки плана материалами
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    Full,
+    Partial,
+    None
+}
+
+impl Coverage {
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Coverage::Full => "полностью",
+            Coverage::Partial => "частично",
+            Coverage::None => "не обеспечена"
+        }
+    }
+}
+
+impl Feasibility {
+
+    pub fn coverage(&self) -> Coverage {
+        if self.buildable >= self.planned {
+            Coverage::Full
+        } else if self.buildable > Decimal::zero() {
+            Coverage::Partial
+        } else {
+            Coverage::None
+        }
+    }
+}
+
+/// Движение материала для распределения
+enum Movement {
+    /// Инвентаризация: остаток на начало даты заменяется посчитанным количеством
+    Count(Decimal),
+    /// Поступление (+) или уже распределенный расход (-)
+    Flow(Decimal)
+}
+
+type Movements<'a> = HashMap<&'a String, Vec<(NaiveDate, Movement)>>;
+
+/// Сколько материала можно забрать на дату date, не уводя в минус накопленный остаток ни на одну дату начиная с нее.
+/// Следующая инвентаризация заменяет остаток посчитанным, поэтому расход после нее не ограничивает
+fn available(movements: &Movements, material: &String, date: NaiveDate) -> Decimal {
+    let Some(movements) = movements.get(material) else {
+        return Decimal::zero();
+    };
+    let mut sorted: Vec<_> = movements.iter().collect();
+    // в пределах даты сначала инвентаризация, затем поступления, чтобы промежуточный остаток не был ниже остатка на конец даты
+    sorted.sort_by_key(|(d, movement)| (*d, match movement {
+        Movement::Count(_) => 0,
+        Movement::Flow(qty) if *qty >= Decimal::zero() => 1,
+        Movement::Flow(_) => 2
+    }));
+    let mut sorted = sorted.into_iter().peekable();
+    let mut balance = Decimal::zero();
+    while let Some((_, movement)) = sorted.next_if(|(d, _)| *d <= date) {
+        match movement {
+            Movement::Count(counted) => balance = *counted,
+            Movement::Flow(qty) => balance += qty
+        }
+    }
+    let mut result = balance;
+    for (_, movement) in sorted {
+        let Movement::Flow(qty) = movement else {
+            break;
+        };
+        balance += qty;
+        result = result.min(balance);
+    }
+    result
+}
+
+/// Распределение материалов по строкам плана в заданном порядке:
+/// каждая строка забирает материал только под то количество товара, которое можно собрать
+fn allocate(init_data: &InitialData, plan: Vec<(Option<u32>, &PurchasePlanItem)>) -> Result<Vec<Feasibility>, String> {
+    let mut movements: Movements = HashMap::new();
+    for count in calculate_stock_counts(&[], init_data) {
+        movements.entry(count.material).or_default().push((count.date, Movement::Count(count.counted)));
+    }
+    for mi in init_data.purchase_orders.iter().flat_map(|po| po.items.iter()) {
+        movements.entry(&mi.material).or_default().push((mi.date, Movement::Flow(mi.qty)));
+    }
+
+    let mut result = vec![];
+    for (priority, ppi) in plan {
+        let sp = find_specification(ppi.product_name.clone(), &init_data.specifications)?;
+        let mut buildable = ppi.qty;
+        let mut limiting_material = None;
+        for spi in sp.items.iter().filter(|spi| spi.qty > Decimal::zero()) {
+            let possible = (available(&movements, &spi.material_name, ppi.date) / spi.qty).floor().max(Decimal::zero());
+            if possible < buildable {
+                buildable = possible;
+                limiting_material = Some(spi.material_name.clone());
+            }
+        }
+        for spi in sp.items.iter() {
+            movements.entry(&spi.material_name).or_default().push((ppi.date, Movement::Flow(-buildable * spi.qty)));
+        }
+        result.push(Feasibility {
+            priority,
+            product_name: ppi.product_name.clone(),
+            date: ppi.date,
+            planned: ppi.qty,
+            buildable,
+            limiting_material,
+        });
+    }
+    Ok(result)
+}
+
+/// Строки плана обрабатываются по дате (при равной дате - в порядке файла плана)
+pub fn calculate_feasibility(init_data: &InitialData) -> Result<Vec<Feasibility>, String> {
+    let mut plan: Vec<_> = init_data.purchase_plan_items.iter().map(|ppi| (None, ppi)).collect();
+    plan.sort_by_key(|(_, ppi)| ppi.date);
+    allocate(init_data, plan)
+}
+
+/// Распределение дефицитных материалов по приоритету товара (1 - высший, товары без приоритета - последними),
+/// при равном приоритете - по дате
+pub fn calculate_allocation(init_data: &InitialData, priorities: &[(String, u32)]) -> Result<Vec<Feasibility>, String> {
+    let mut plan: Vec<_> = init_data.purchase_plan_items.iter()
+        .map(|ppi| (priorities.iter().find(|(product, _)| *product == ppi.product_name).map(|(_, p)| *p), ppi))
+        .collect();
+    plan.sort_by_key(|(priority, ppi)| (priority.unwrap_or(u32::MAX), ppi.date));
+    allocate(init_data, plan)
+}
+
+/// Лист выполнимости плана: строки, которые нельзя выполнить полностью, выделены.
+/// with_priority - колонки приоритета и обеспечения для листа распределения по приоритетам
+pub fn write_to_worksheet(sheet: &mut Worksheet, feasibility: &[Feasibility], settings: &Settings, with_priority: bool) {
+    let num_format = settings.period.num_format();
+    let mut headers = vec!["Товар", "Дата", "План", "Можно собрать", "Не хватает", "Ограничивающий материал"];
+    if with_priority {
+        headers.insert(0, "Приоритет");
+        headers.push("Обеспечение");
+    }
+    let mut rows = vec![];
+    for f in feasibility {
+        let mut row = vec![
+            XlsCell::text(&f.product_name),
+            XlsCell::date(f.date, num_format),
+            XlsCell::decimal(f.planned),
+            XlsCell::decimal(f.buildable),
+            XlsCell::decimal(f.planned - f.buildable),
+            XlsCell::text(f.limiting_material.as_deref().unwrap_or_default()),
+        ];
+        if with_priority {
+            row.insert(0, f.priority.map_or_else(XlsCell::empty, |p| XlsCell::decimal(Decimal::from(p))));
+            row.push(XlsCell::text(f.coverage().name()));
+        }
+        let background = match f.coverage() {
+            Coverage::Full => None,
+            Coverage::Partial => Some(0xFFEB9C),
+            Coverage::None => Some(0xFFC7CE)
+        };
+        if let Some(color) = background {
+            row = row.into_iter().map(|c| c.with_format(XlsCellFormat::Background(Color::RGB(color)))).collect();
+        }
+        rows.push(row);
+    }
+    let matrix = XlsMatrix::table(&headers, rows, if with_priority { 2 } else { 1 });
+    matrix.write_to_worksheet(sheet);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use crate::init_data::{InitialData, OrderStatus, PurchaseOrder, PurchasePlanItem, Specification, SpecificationItem};
+    use crate::orders_plan::MaterialInfo;
+    use super::calculate_feasibility;
+
+    fn date(d: (i32, u32, u32)) -> NaiveDate {
+        NaiveDate::from_ymd_opt(d.0, d.1, d.2).unwrap()
+    }
+
+    fn mi(material: &str, d: (i32, u32, u32), qty: i64) -> MaterialInfo {
+        MaterialInfo { date: date(d), material: material.to_string(), qty: Decimal::from(qty) }
+    }
+
+    fn plan(d: (i32, u32, u32), qty: i64) -> PurchasePlanItem {
+        PurchasePlanItem { product_name: "Товар".to_string(), date: date(d), qty: Decimal::from(qty) }
+    }
+
+    fn data() -> InitialData {
+        let mut data = InitialData::empty();
+        data.specifications = vec![Specification {
+            product_name: "Товар".to_string(),
+            date_from: date((2024, 1, 1)),
+            items: vec![SpecificationItem { material_name: "A".to_string(), qty: Decimal::ONE }]
+        }];
+        data
+    }
+
+    #[test]
+    fn later_count_replaces_balance() {
+        let mut data = data();
+        data.stocks = vec![mi("A", (2024, 4, 1), 100), mi("A", (2024, 4, 15), 10)];
+        data.purchase_plan_items = vec![plan((2024, 4, 8), 200), plan((2024, 4, 22), 50)];
+
+        let result = calculate_feasibility(&data).unwrap();
+        assert_eq!(result[0].buildable, Decimal::from(100));
+        // после инвентаризации 15.04 в наличии только 10, нехватка в первой строке на это не влияет
+        assert_eq!(result[1].buildable, Decimal::from(10));
+    }
+
+    #[test]
+    fn receipt_after_count_is_available() {
+        let mut data = data();
+        data.stocks = vec![mi("A", (2024, 4, 1), 100), mi("A", (2024, 4, 15), 10)];
+        data.purchase_orders = vec![PurchaseOrder {
+            name: "1".to_string(),
+            items: vec![mi("A", (2024, 4, 15), 30)],
+            status: OrderStatus::Open,
+            order_date: None
+        }];
+        data.purchase_plan_items = vec![plan((2024, 4, 8), 80), plan((2024, 4, 22), 50)];
+
+        let result = calculate_feasibility(&data).unwrap();
+        assert_eq!(result[0].buildable, Decimal::from(80));
+        assert_eq!(result[1].buildable, Decimal::from(40));
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::Zero;
+use rust_decimal::prelude::ToPrimitive;
+use rust_xlsxwriter::{ColNum, Color, ConditionalFormatCell, ConditionalFormatCellRule, Format, FormatBorder, Formula, RowNum, Worksheet};
+use rust_xlsxwriter::utility::row_col_to_cell;
+use crate::init_data::InitialData;
+use crate::orders_plan::{append_qty, MaterialInfo};
+use crate::projection::Projection;
+use crate::report::{get_horizon, PAST_DUE_HEADER};
+use crate::report_diff::{CHANGED_COLOR, ReportDiff};
+use crate::settings::{OverdueMode, Settings};
+use crate::stock_count::{calculate_stock_counts, stock_movements};
+
+const FIRST_ROW: RowNum = 1;
+const MATERIAL_COL: ColNum = 1;
+const KIND_COL: ColNum = 2;
+const FIRST_DATE_COL: ColNum = 3;
+const ROW_NAMES: [&str; 4] = ["Корректировка остатков", "Поступления", "Потребность", "Остаток"];
+
+/// Отчет "Состояние заказов" с формулами: по материалу строки корректировки остатков по инвентаризациям
+/// (посчитанный минус ожидаемый остаток), поступлений, потребности
+/// и накопленного остатка = предыдущий остаток + корректировка + поступления - потребность.
+/// Изменение количества в первых трех строках сразу пересчитывает остаток и его цвет:
+/// раскраска задана условным форматированием, а не цветом шрифта ячейки.
+/// Изменения относительно предыдущего расчета выделяются фоном, как в отчете без формул
+pub fn write_to_worksheet(sheet: &mut Worksheet, projection: &Projection, requirements: &Vec<MaterialInfo>,
+                          init_data: &InitialData, diff: Option<&ReportDiff>, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let today = Utc::now().naive_utc().date();
+    let now = settings.period.start(&today);
+    let next = settings.period.next(&today);
+    let dates = &projection.dates;
+
+    let stock_movements = stock_movements(&calculate_stock_counts(requirements, init_data));
+    let mut stocks_map = HashMap::new();
+    append_qty(&mut stocks_map, &stock_movements);
+    let mut receipts_map = HashMap::new();
+    for po in &init_data.purchase_orders {
+        append_qty(&mut receipts_map, &po.items);
+    }
+    let mut requirements_map = HashMap::new();
+    append_qty(&mut requirements_map, requirements);
+    let changed: HashSet<(&String, NaiveDate)> = diff.iter()
+        .flat_map(|d| d.changed_cells.iter())
+        .map(|c| (&c.material, c.date))
+        .collect();
+    // просроченные поступления, не учтенные в остатке, - отдельной колонкой после дат в строке поступлений
+    let past_due_col = (settings.overdue == OverdueMode::PastDue && !init_data.overdue_receipts.is_empty())
+        .then_some(FIRST_DATE_COL + dates.len() as ColNum);
+
+    let header = Format::new().set_bold().set_border(FormatBorder::Thin);
+    let date_header = header.clone().set_num_format(settings.period.num_format());
+    let bordered = Format::new().set_border(FormatBorder::Thin);
+    let grey = bordered.clone().set_font_color(Color::RGB(0xDDDDDD));
+    let now_bordered = bordered.clone().set_background_color(Color::RGB(0xEEEEEE));
+    let now_grey = grey.clone().set_background_color(Color::RGB(0xEEEEEE));
+
+    sheet.write_with_format(FIRST_ROW, MATERIAL_COL, "Материал", &header)?;
+    sheet.write_with_format(FIRST_ROW, KIND_COL, "Показатель", &header)?;
+    for (i, date) in dates.iter().enumerate() {
+        sheet.write_with_format(FIRST_ROW, FIRST_DATE_COL + i as ColNum, date, &date_header)?;
+    }
+    if let Some(col) = past_due_col {
+        sheet.write_with_format(FIRST_ROW, col, PAST_DUE_HEADER, &header)?;
+    }
+
+    let red = Format::new().set_font_color(Color::Red);
+    let green = Format::new().set_font_color(Color::Green);
+    let orange = Format::new().set_font_color(Color::Orange);
+
+    for (m, material) in projection.materials.iter().enumerate() {
+        let first_row = FIRST_ROW + 1 + (m * ROW_NAMES.len()) as RowNum;
+        let balance_row = first_row + 3;
+        let horizon = get_horizon(init_data, material, &today, settings)?;
+        // дефицит за горизонтом, который еще можно закрыть заказом в текущем интервале
+        let mut last_order_date = None;
+        for date in dates.iter().filter(|d| **d >= horizon) {
+            if init_data.get_order_by_date(material, date)? < next {
+                last_order_date = Some(*date);
+            }
+        }
+
+        let material_format = match diff.and_then(|d| d.material_color(material)) {
+            Some(color) => bordered.clone().set_background_color(Color::RGB(color)),
+            None => bordered.clone()
+        };
+        for (k, name) in ROW_NAMES.iter().enumerate() {
+            sheet.write_with_format(first_row + k as RowNum, MATERIAL_COL, material, &material_format)?;
+            sheet.write_with_format(first_row + k as RowNum, KIND_COL, *name, &bordered)?;
+        }
+        for (i, date) in dates.iter().enumerate() {
+            let col = FIRST_DATE_COL + i as ColNum;
+            let is_now = *date == now;
+            let value_format = if is_now { &now_bordered } else { &bordered };
+            let key = (*date, material);
+            let values = [
+                stocks_map.get(&key).copied().unwrap_or_default(),
+                receipts_map.get(&key).copied().unwrap_or_default(),
+                requirements_map.get(&key).map(|r| -r).unwrap_or_default(),
+            ];
+            for (k, value) in values.iter().enumerate() {
+                let row = first_row + k as RowNum;
+                if value.is_zero() {
+                    sheet.write_blank(row, col, value_format)?;
+                } else {
+                    sheet.write_with_format(row, col, value.to_f64(), value_format)?;
+                }
+            }
+
+            let previous = if i == 0 { String::new() } else { format!("{}+", row_col_to_cell(balance_row, col - 1)) };
+            let formula = Formula::new(format!("={}{}+{}-{}", previous,
+                row_col_to_cell(first_row, col), row_col_to_cell(first_row + 1, col), row_col_to_cell(first_row + 2, col)))
+                .set_result(projection.balances[m][i].normalize().to_string());
+            let in_colored_range = *date < horizon || last_order_date.is_some_and(|d| *date <= d);
+            let balance_format = match (in_colored_range, is_now) {
+                (true, true) => &now_bordered,
+                (true, false) => &bordered,
+                (false, true) => &now_grey,
+                (false, false) => &grey
+            };
+            if changed.contains(&(material, *date)) {
+                sheet.write_formula_with_format(balance_row, col, formula, &balance_format.clone().set_background_color(Color::RGB(CHANGED_COLOR)))?;
+            } else {
+                sheet.write_formula_with_format(balance_row, col, formula, balance_format)?;
+            }
+        }
+        if let Some(col) = past_due_col {
+            let qty: Decimal = init_data.overdue_receipts.iter().filter(|r| r.material == *material).map(|r| r.qty).sum();
+            for k in 0..ROW_NAMES.len() {
+                sheet.write_blank(first_row + k as RowNum, col, &bordered)?;
+            }
+            if qty > Decimal::zero() {
+                sheet.write_with_format(first_row + 1, col, qty.to_f64(), &bordered.clone().set_background_color(Color::RGB(0xFFC7CE)))?;
+            }
+        }
+
+        let horizon_col = FIRST_DATE_COL + dates.iter().take_while(|d| **d < horizon).count() as ColNum;
+        if horizon_col > FIRST_DATE_COL {
+            sheet.add_conditional_format(balance_row, FIRST_DATE_COL, balance_row, horizon_col - 1,
+                &ConditionalFormatCell::new().set_rule(ConditionalFormatCellRule::LessThan(0)).set_format(&red))?;
+            sheet.add_conditional_format(balance_row, FIRST_DATE_COL, balance_row, horizon_col - 1,
+                &ConditionalFormatCell::new().set_rule(ConditionalFormatCellRule::GreaterThanOrEqualTo(0)).set_format(&green))?;
+        }
+        if let Some(last_order_date) = last_order_date {
+            let last_col = FIRST_DATE_COL + dates.iter().take_while(|d| **d <= last_order_date).count() as ColNum - 1;
+            sheet.add_conditional_format(balance_row, horizon_col, balance_row, last_col,
+                &ConditionalFormatCell::new().set_rule(ConditionalFormatCellRule::LessThan(0)).set_format(&orange))?;
+            sheet.add_conditional_format(balance_row, horizon_col, balance_row, last_col,
+                &ConditionalFormatCell::new().set_rule(ConditionalFormatCellRule::GreaterThanOrEqualTo(0))
+                    .set_format(Format::new().set_font_color(Color::RGB(0xDDDDDD))))?;
+        }
+    }
+
+    let last_row = FIRST_ROW + (projection.materials.len() * ROW_NAMES.len()) as RowNum;
+    sheet.set_freeze_panes(FIRST_ROW + 1, FIRST_DATE_COL)?;
+    sheet.autofilter(FIRST_ROW, MATERIAL_COL, last_row, KIND_COL)?;
+    sheet.set_landscape();
+    sheet.set_print_fit_to_pages(1, 0);
+    sheet.set_repeat_rows(FIRST_ROW, FIRST_ROW)?;
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use chrono::{Local, NaiveDate};
+use rusqlite::{params, Connection};
+use crate::init_data::InitialData;
+use crate::projection::Projection;
+use crate::settings::Settings;
+
+/// Интервал и единица срока доставки хранятся значениями name(), как в -period=
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY,
+        run_time TEXT NOT NULL,
+        as_of_date TEXT NOT NULL,
+        period TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS plan_items (run_id INTEGER NOT NULL, product_name TEXT NOT NULL, date TEXT NOT NULL, qty TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS stocks (run_id INTEGER NOT NULL, material TEXT NOT NULL, date TEXT NOT NULL, qty TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS purchase_order_items (run_id INTEGER NOT NULL, order_name TEXT NOT NULL, material TEXT NOT NULL, date TEXT NOT NULL, qty TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS specification_items (run_id INTEGER NOT NULL, product_name TEXT NOT NULL, date_from TEXT NOT NULL, material TEXT NOT NULL, qty TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS delivery_times (run_id INTEGER NOT NULL, material TEXT NOT NULL, qty INTEGER NOT NULL, unit TEXT NOT NULL, supplier TEXT);
+    CREATE TABLE IF NOT EXISTS projection (run_id INTEGER NOT NULL, material TEXT NOT NULL, date TEXT NOT NULL, balance TEXT NOT NULL);
+    CREATE INDEX IF NOT EXISTS projection_material ON projection (material, date);
+";
+
+fn open(path: &str) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    Ok(connection)
+}
+
+/// Сохранение исходных данных и прогноза запуска. Количества хранятся текстом, чтобы не терять точность Decimal.
+pub fn save_run(path: &str, init_data: &InitialData, projection: &Projection, settings: &Settings) -> rusqlite::Result<i64> {
+    let mut connection = open(path)?;
+    let tx = connection.transaction()?;
+    let now = Local::now();
+    tx.execute("INSERT INTO runs (run_time, as_of_date, period) VALUES (?1, ?2, ?3)",
+               params![now.format("%Y-%m-%d %H:%M:%S").to_string(), now.date_naive(), settings.period.name()])?;
+    let run_id = tx.last_insert_rowid();
+    {
+        let mut stmt = tx.prepare("INSERT INTO plan_items VALUES (?1, ?2, ?3, ?4)")?;
+        for ppi in init_data.purchase_plan_items.iter() {
+            stmt.execute(params![run_id, ppi.product_name, ppi.date, ppi.qty.to_string()])?;
+        }
+        let mut stmt = tx.prepare("INSERT INTO stocks VALUES (?1, ?2, ?3, ?4)")?;
+        for mi in init_data.stocks.iter() {
+            stmt.execute(params![run_id, mi.material, mi.date, mi.qty.to_string()])?;
+        }
+        let mut stmt = tx.prepare("INSERT INTO purchase_order_items VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        for po in init_data.purchase_orders.iter() {
+            for mi in po.items.iter() {
+                stmt.execute(params![run_id, po.name, mi.material, mi.date, mi.qty.to_string()])?;
+            }
+        }
+        let mut stmt = tx.prepare("INSERT INTO specification_items VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        for sp in init_data.specifications.iter() {
+            for spi in sp.items.iter() {
+                stmt.execute(params![run_id, sp.product_name, sp.date_from, spi.material_name, spi.qty.to_string()])?;
+            }
+        }
+        let mut stmt = tx.prepare("INSERT INTO delivery_times VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        for dt in init_data.delivery_times.iter() {
+            stmt.execute(params![run_id, dt.material_name, dt.qty, dt.unit.name(), dt.supplier])?;
+        }
+        let mut stmt = tx.prepare("INSERT INTO projection VALUES (?1, ?2, ?3, ?4)")?;
+        for (row, material) in projection.materials.iter().enumerate() {
+            for (col, date) in projection.dates.iter().enumerate() {
+                stmt.execute(params![run_id, material, date, projection.balances[row][col].to_string()])?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(run_id)
+}
+
+/// Как менялся прогнозный остаток материала на дату от запуска к запуску.
+/// Для каждого запуска берется остаток интервала, в который попадает дата.
+pub fn print_material_history(path: &str, material: &str, date: &NaiveDate) -> rusqlite::Result<()> {
+    let connection = open(path)?;
+    let mut stmt = connection.prepare("
+        SELECT r.run_time, r.as_of_date, r.period,
+               (SELECT p.balance FROM projection p
+                WHERE p.run_id = r.id AND p.material = ?1 AND p.date <= ?2
+                ORDER BY p.date DESC LIMIT 1)
+        FROM runs r
+        ORDER BY r.run_time")?;
+    let rows = stmt.query_map(params![material, date], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, NaiveDate>(1)?, row.get::<_, String>(2)?, row.get::<_, Option<String>>(3)?))
+    })?;
+
+    println!("Прогноз остатка \"{}\" на {}:", material, date.format("%d.%m.%Y"));
+    println!("{:<20} {:<12} {:<8} {:>14}", "Запуск", "На дату", "Интервал", "Остаток");
+    let mut previous: Option<String> = None;
+    for row in rows {
+        let (run_time, as_of_date, period, balance) = row?;
+        let balance = balance.unwrap_or("-".to_string());
+        let mark = if previous.is_some() && previous.as_ref() != Some(&balance) { " *" } else { "" };
+        println!("{:<20} {:<12} {:<8} {:>14}{}", run_time, as_of_date.format("%d.%m.%Y").to_string(), period, balance, mark);
+        previous = Some(balance);
+    }
+    Ok(())
+}
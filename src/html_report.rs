@@ -0,0 +1,155 @@
+use std::error::Error;
+use std::fs;
+use chrono::NaiveDate;
+use rust_xlsxwriter::Color;
+use crate::init_data::InitialData;
+use crate::projection::Projection;
+use crate::report::collect_shortages;
+use crate::settings::Settings;
+use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsCellValue, XlsMatrix};
+
+pub(crate) const STYLE: &str = r#"
+body { font-family: Calibri, Arial, sans-serif; font-size: 14px; margin: 8px; }
+h2 { font-size: 18px; margin: 16px 0 8px; }
+input { font-size: 14px; padding: 4px 8px; width: 320px; max-width: 100%; margin-bottom: 8px; }
+.scroll { overflow: auto; max-height: 80vh; border: 1px solid #ccc; }
+table { border-collapse: separate; border-spacing: 0; }
+td, th { border-right: 1px solid #ccc; border-bottom: 1px solid #ccc; padding: 2px 6px; white-space: nowrap; background: #fff; }
+td.num { text-align: right; }
+.matrix tr:first-child td { position: sticky; top: 0; z-index: 1; font-weight: bold; }
+.matrix td:first-child { position: sticky; left: 0; z-index: 1; }
+.matrix tr:first-child td:first-child { z-index: 2; }
+.summary th { cursor: pointer; background: #eee; position: sticky; top: 0; }
+.summary th::after { content: " \2195"; color: #999; }
+"#;
+
+const SCRIPT: &str = r#"
+function filterRows(text) {
+  text = text.toLowerCase();
+  document.querySelectorAll('tr[data-material]').forEach(function (tr) {
+    tr.style.display = tr.dataset.material.toLowerCase().indexOf(text) >= 0 ? '' : 'none';
+  });
+}
+function sortTable(th) {
+  var table = th.closest('table'), body = table.tBodies[0];
+  var col = Array.prototype.indexOf.call(th.parentNode.children, th);
+  var asc = th.dataset.asc !== 'true';
+  th.dataset.asc = asc;
+  var rows = Array.prototype.slice.call(body.rows);
+  rows.sort(function (a, b) {
+    var x = a.cells[col].dataset.value, y = b.cells[col].dataset.value;
+    var nx = parseFloat(x), ny = parseFloat(y);
+    var r = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+    return asc ? r : -r;
+  });
+  rows.forEach(function (r) { body.appendChild(r); });
+}
+"#;
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn css_color(color: &Color) -> String {
+    match color {
+        Color::RGB(rgb) => format!("#{:06X}", rgb),
+        Color::Red => "#FF0000".to_string(),
+        Color::Green => "#008000".to_string(),
+        Color::Orange => "#FF6600".to_string(),
+        Color::Gray => "#808080".to_string(),
+        Color::Yellow => "#FFFF00".to_string(),
+        Color::White => "#FFFFFF".to_string(),
+        _ => "#000000".to_string()
+    }
+}
+
+fn format_date(date: &NaiveDate, num_format: &str) -> String {
+    match num_format {
+        "mm.yyyy" => date.format("%m.%Y").to_string(),
+        _ => date.format("%d.%m.%Y").to_string()
+    }
+}
+
+/// Ячейка матрицы в html с теми же цветами, что и в xlsx
+fn cell_to_html(cell: &XlsCell) -> String {
+    let mut style = String::new();
+    let mut num_format = "dd.mm.yyyy";
+    for f in &cell.formats {
+        match f {
+            XlsCellFormat::FontColor(color) => style.push_str(&format!("color:{};", css_color(color))),
+            XlsCellFormat::Background(color) => style.push_str(&format!("background:{};", css_color(color))),
+            XlsCellFormat::NumFormat(nf) => num_format = nf,
+            XlsCellFormat::Bordered => ()
+        }
+    }
+    let (class, text) = match &cell.cell_value {
+        XlsCellValue::None => ("", String::new()),
+        XlsCellValue::Decimal(d) => (" class=\"num\"", d.normalize().to_string()),
+        XlsCellValue::Date(d) => ("", format_date(d, num_format)),
+        XlsCellValue::String(s) => ("", escape(s))
+    };
+    format!("<td{} style=\"{}\">{}</td>", class, style, text)
+}
+
+fn sortable_cell(text: &str, value: &str) -> String {
+    format!("<td data-value=\"{}\">{}</td>", escape(value), escape(text))
+}
+
+/// Сводка дефицитов: материал, первый дефицит, минимальный остаток, срок доставки
+fn shortage_summary(projection: &Projection, init_data: &InitialData, settings: &Settings) -> Result<String, String> {
+    let mut html = String::from("<table class=\"summary\"><thead><tr>\
+        <th onclick=\"sortTable(this)\">Материал</th>\
+        <th onclick=\"sortTable(this)\">Первый дефицит</th>\
+        <th onclick=\"sortTable(this)\">Минимальный остаток</th>\
+        <th onclick=\"sortTable(this)\">В горизонте срока доставки</th>\
+        <th onclick=\"sortTable(this)\">Срок доставки, дн.</th>\
+        </tr></thead><tbody>");
+    for shortage in collect_shortages(projection, init_data, settings)? {
+        let inside_horizon = shortage.inside_lead_time_horizon;
+        html.push_str(&format!("<tr data-material=\"{}\">{}{}{}{}{}</tr>",
+            escape(&shortage.material),
+            sortable_cell(&shortage.material, &shortage.material),
+            sortable_cell(&shortage.first_shortage.format("%d.%m.%Y").to_string(), &shortage.first_shortage.format("%Y%m%d").to_string()),
+            sortable_cell(&shortage.min_balance.normalize().to_string(), &shortage.min_balance.to_string()),
+            sortable_cell(if inside_horizon { "да" } else { "нет" }, if inside_horizon { "1" } else { "0" }),
+            sortable_cell(&shortage.lead_time_days.to_string(), &shortage.lead_time_days.to_string())));
+    }
+    html.push_str("</tbody></table>");
+    Ok(html)
+}
+
+/// Отчет в одном html файле без внешних ресурсов: матрица состояния заказов и сводка дефицитов.
+/// toolbar - дополнительная разметка в начале страницы (кнопки веб-интерфейса)
+pub fn build_html(matrix: &XlsMatrix, projection: &Projection, init_data: &InitialData, settings: &Settings, toolbar: &str) -> Result<String, String> {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html><html lang=\"ru\"><head><meta charset=\"utf-8\">\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+        <title>Состояние заказов</title>");
+    html.push_str(&format!("<style>{}</style><script>{}</script></head><body>", STYLE, SCRIPT));
+    html.push_str(toolbar);
+    html.push_str("<input type=\"search\" placeholder=\"Фильтр по материалу\" oninput=\"filterRows(this.value)\">");
+
+    html.push_str("<h2>Дефициты</h2><div class=\"scroll\">");
+    html.push_str(&shortage_summary(projection, init_data, settings)?);
+    html.push_str("</div>");
+
+    html.push_str("<h2>Состояние заказов</h2><div class=\"scroll\"><table class=\"matrix\">");
+    for (row_num, row) in matrix.rows.iter().enumerate() {
+        if row_num == 0 {
+            html.push_str("<tr>");
+        } else {
+            html.push_str(&format!("<tr data-material=\"{}\">", escape(&projection.materials[row_num - 1])));
+        }
+        for cell in row {
+            html.push_str(&cell_to_html(cell));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table></div></body></html>");
+    Ok(html)
+}
+
+pub fn save_html(path: &str, matrix: &XlsMatrix, projection: &Projection, init_data: &InitialData, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    fs::write(path, build_html(matrix, projection, init_data, settings, "")?)?;
+    Ok(())
+}
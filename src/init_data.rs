@@ -1,6 +1,9 @@
-use chrono::NaiveDate;
+use chrono::{Days, NaiveDate};
 use rust_decimal::Decimal;
+use crate::calendar::{Calendar, LeadTimeUnit};
 use crate::orders_plan::MaterialInfo;
+use crate::lead_time_check::EarlyReceipt;
+use crate::overdue::OverdueReceipt;
 
 #[derive(Debug)]
 pub struct PurchasePlanItem {
@@ -21,33 +24,325 @@ pub struct SpecificationItem{
     pub(crate) qty: Decimal
 }
 
+/// Состояние заказа поставщику по подтвержденным поступлениям
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    Open,
+    PartiallyReceived,
+    Closed,
+    Cancelled
+}
+
+impl OrderStatus {
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrderStatus::Open => "открыт",
+            OrderStatus::PartiallyReceived => "частично получен",
+            OrderStatus::Closed => "закрыт",
+            OrderStatus::Cancelled => "отменен"
+        }
+    }
+}
+
+/// Подтверждение поступления по заказу поставщику из файла "Поступления"
+#[derive(Debug)]
+pub struct Receipt {
+    pub(crate) order: String,
+    /// Материал; None - весь заказ
+    pub(crate) material: Option<String>,
+    /// Полученное количество; None - отмена строк материала или всего заказа
+    pub(crate) received: Option<Decimal>
+}
+
+/// Заказ поставщику. items - открытые (еще не полученные) количества по датам поступления
 #[derive(Debug)]
 pub struct PurchaseOrder{
     pub(crate) name: String,
-    pub(crate) items: Vec<MaterialInfo>
+    pub(crate) items: Vec<MaterialInfo>,
+    pub(crate) status: OrderStatus,
+    /// Дата размещения заказа из заголовка файла заказа
+    pub(crate) order_date: Option<NaiveDate>
+}
+
+impl PurchaseOrder {
+
+    /// Уменьшает строки заказа на полученные количества (полученное списывается с самых ранних поступлений материала),
+    /// убирает отмененные строки и определяет статус заказа
+    pub fn apply_receipts(&mut self, receipts: &[Receipt]) {
+        let mut received_any = false;
+        let mut cancelled_any = false;
+        for receipt in receipts.iter().filter(|r| r.order == self.name) {
+            let matches = |mi: &MaterialInfo| receipt.material.as_ref().is_none_or(|m| *m == mi.material);
+            let Some(mut received) = receipt.received else {
+                self.items.retain(|mi| !matches(mi));
+                cancelled_any = true;
+                continue;
+            };
+            received_any = true;
+            self.items.sort_by_key(|mi| mi.date);
+            for mi in self.items.iter_mut().filter(|mi| matches(mi)) {
+                let qty = mi.qty.min(received);
+                mi.qty -= qty;
+                received -= qty;
+            }
+            self.items.retain(|mi| mi.qty > Decimal::ZERO);
+        }
+        self.status = match (self.items.is_empty(), received_any, cancelled_any) {
+            (true, false, true) => OrderStatus::Cancelled,
+            (true, true, _) => OrderStatus::Closed,
+            (false, true, _) => OrderStatus::PartiallyReceived,
+            _ => OrderStatus::Open
+        };
+    }
 }
 
 #[derive(Debug)]
 pub struct DeliveryTime {
     pub(crate) material_name: String,
-    pub(crate) weeks: u32
+    pub(crate) qty: u32,
+    pub(crate) unit: LeadTimeUnit,
+    pub(crate) supplier: Option<String>,
+    /// Страховой запас, ниже которого остаток опускаться не должен
+    pub(crate) safety_stock: Option<Decimal>
+}
+
+impl DeliveryTime {
+
+    pub fn days(&self) -> u32 {
+        match self.unit {
+            LeadTimeUnit::Weeks => self.qty * 7,
+            _ => self.qty
+        }
+    }
 }
 
 pub struct InitialData {
     pub(crate) purchase_orders: Vec<PurchaseOrder>,
     pub(crate) delivery_times: Vec<DeliveryTime>,
+    pub(crate) calendars: Vec<Calendar>,
     pub(crate) specifications: Vec<Specification>,
     pub(crate) stocks: Vec<MaterialInfo>,
-    pub(crate) purchase_plan_items: Vec<PurchasePlanItem>
+    pub(crate) purchase_plan_items: Vec<PurchasePlanItem>,
+    /// Просроченные поступления по заказам, уже перенесенные или исключенные согласно settings.overdue
+    pub(crate) overdue_receipts: Vec<OverdueReceipt>,
+    /// Строки заказов, поступление по которым ожидается раньше, чем позволяет срок доставки
+    pub(crate) early_receipts: Vec<EarlyReceipt>
 }
 
 impl InitialData {
-    pub fn get_delivery_weeks(&self, material_name: &str) -> Result<usize,String> {
+
+    /// Пустые исходные данные для тестов
+    #[cfg(test)]
+    pub(crate) fn empty() -> Self {
+        InitialData {
+            purchase_orders: vec![],
+            delivery_times: vec![],
+            calendars: vec![],
+            specifications: vec![],
+            stocks: vec![],
+            purchase_plan_items: vec![],
+            overdue_receipts: vec![],
+            early_receipts: vec![]
+        }
+    }
+
+    pub fn get_delivery_time(&self, material_name: &str) -> Result<&DeliveryTime,String> {
         for dt in &self.delivery_times {
             if dt.material_name == material_name {
-                return Ok(dt.weeks as usize);
+                return Ok(dt);
             }
         }
         Err(format!(r#"Не найден срок доставки для материала '{}'"#,material_name))
     }
+
+    fn get_calendars(&self, dt: &DeliveryTime) -> Vec<&Calendar> {
+        self.calendars.iter().filter(|c| c.supplier.is_none() || c.supplier == dt.supplier).collect()
+    }
+
+    /// Дата поступления материала, заказанного в дату order_date
+    pub fn get_arrival_date(&self, material_name: &str, order_date: &NaiveDate) -> Result<NaiveDate,String> {
+        let dt = self.get_delivery_time(material_name)?;
+        let calendars = self.get_calendars(dt);
+        let mut date = *order_date;
+        let mut days_left = dt.days();
+        while days_left > 0 {
+            date = date + Days::new(1);
+            if dt.unit.counts(&date, &calendars) {
+                days_left -= 1;
+            }
+        }
+        Ok(date)
+    }
+
+    /// Последняя дата заказа материала, при которой он поступит не позже need_date
+    pub fn get_order_by_date(&self, material_name: &str, need_date: &NaiveDate) -> Result<NaiveDate,String> {
+        let dt = self.get_delivery_time(material_name)?;
+        let calendars = self.get_calendars(dt);
+        let mut date = *need_date;
+        while dt.days() > 0 && !dt.unit.counts(&date, &calendars) {
+            date = date - Days::new(1);
+        }
+        let mut days_left = dt.days();
+        while days_left > 0 {
+            if dt.unit.counts(&date, &calendars) {
+                days_left -= 1;
+            }
+            date = date - Days::new(1);
+        }
+        Ok(date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use crate::orders_plan::MaterialInfo;
+    use crate::calendar::{Calendar, LeadTimeUnit};
+    use crate::period::Period;
+    use super::{DeliveryTime, InitialData, OrderStatus, PurchaseOrder, Receipt};
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 4, d).unwrap()
+    }
+
+    fn order() -> PurchaseOrder {
+        let mi = |material: &str, d: u32, qty: i64| MaterialInfo { date: date(d), material: material.to_string(), qty: Decimal::from(qty) };
+        PurchaseOrder {
+            name: "100".to_string(),
+            items: vec![mi("A", 15, 30), mi("A", 1, 50), mi("B", 8, 20)],
+            status: OrderStatus::Open,
+            order_date: None
+        }
+    }
+
+    fn received(material: &str, qty: i64) -> Receipt {
+        Receipt { order: "100".to_string(), material: Some(material.to_string()), received: Some(Decimal::from(qty)) }
+    }
+
+    fn open_qty(po: &PurchaseOrder, material: &str) -> Vec<(NaiveDate, Decimal)> {
+        po.items.iter().filter(|mi| mi.material == material).map(|mi| (mi.date, mi.qty)).collect()
+    }
+
+    #[test]
+    fn partial_receipt_reduces_earliest_lines() {
+        let mut po = order();
+        po.apply_receipts(&[received("A", 60)]);
+        assert_eq!(po.status, OrderStatus::PartiallyReceived);
+        assert_eq!(open_qty(&po, "A"), vec![(date(15), Decimal::from(20))]);
+        assert_eq!(open_qty(&po, "B"), vec![(date(8), Decimal::from(20))]);
+    }
+
+    #[test]
+    fn over_receipt_closes_lines_without_negative_quantities() {
+        let mut po = order();
+        po.apply_receipts(&[received("A", 100), received("B", 25)]);
+        assert_eq!(po.status, OrderStatus::Closed);
+        assert!(po.items.is_empty());
+    }
+
+    #[test]
+    fn receipt_against_closed_order_keeps_it_closed() {
+        let mut po = order();
+        po.apply_receipts(&[received("A", 80), received("B", 20), received("A", 10)]);
+        assert_eq!(po.status, OrderStatus::Closed);
+        assert!(po.items.is_empty());
+    }
+
+    #[test]
+    fn receipts_of_other_orders_are_ignored() {
+        let mut po = order();
+        let mut other = received("A", 80);
+        other.order = "200".to_string();
+        po.apply_receipts(&[other]);
+        assert_eq!(po.status, OrderStatus::Open);
+        assert_eq!(po.items.len(), 3);
+    }
+
+    #[test]
+    fn cancellation() {
+        let mut po = order();
+        po.apply_receipts(&[Receipt { order: "100".to_string(), material: Some("B".to_string()), received: None }]);
+        assert_eq!(po.status, OrderStatus::Open);
+        assert!(open_qty(&po, "B").is_empty());
+
+        let mut po = order();
+        po.apply_receipts(&[Receipt { order: "100".to_string(), material: None, received: None }]);
+        assert_eq!(po.status, OrderStatus::Cancelled);
+        assert!(po.items.is_empty());
+    }
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// Материалы: W - 2 недели, WD - 3 рабочих дня, D - 3 календарных дня, S - 3 рабочих дня у поставщика "X".
+    /// Нерабочие дни компании 30.04-01.05.2024, поставщика "X" - 02.05.2024
+    fn lead_time_data() -> InitialData {
+        let dt = |material: &str, qty: u32, unit: LeadTimeUnit, supplier: Option<&str>| DeliveryTime {
+            material_name: material.to_string(), qty, unit, supplier: supplier.map(|s| s.to_string()), safety_stock: None
+        };
+        let mut data = InitialData::empty();
+        data.delivery_times = vec![
+            dt("W", 2, LeadTimeUnit::Weeks, None),
+            dt("WD", 3, LeadTimeUnit::WorkingDays, None),
+            dt("D", 3, LeadTimeUnit::Days, None),
+            dt("S", 3, LeadTimeUnit::WorkingDays, Some("X")),
+        ];
+        data.calendars = vec![
+            Calendar { supplier: None, holidays: vec![(ymd(2024, 4, 30), ymd(2024, 5, 1))] },
+            Calendar { supplier: Some("X".to_string()), holidays: vec![(ymd(2024, 5, 2), ymd(2024, 5, 2))] },
+        ];
+        data
+    }
+
+    #[test]
+    fn arrival_in_weeks() {
+        let data = lead_time_data();
+        assert_eq!(data.get_arrival_date("W", &ymd(2024, 4, 1)), Ok(ymd(2024, 4, 15)));
+        assert_eq!(data.get_order_by_date("W", &ymd(2024, 4, 15)), Ok(ymd(2024, 4, 1)));
+    }
+
+    #[test]
+    fn working_days_skip_weekends() {
+        let data = lead_time_data();
+        // пятница + 3 рабочих дня = среда
+        assert_eq!(data.get_arrival_date("WD", &ymd(2024, 4, 5)), Ok(ymd(2024, 4, 10)));
+        assert_eq!(data.get_order_by_date("WD", &ymd(2024, 4, 10)), Ok(ymd(2024, 4, 7)));
+    }
+
+    #[test]
+    fn lead_time_crosses_holidays_and_period_boundaries() {
+        let data = lead_time_data();
+        let order_date = ymd(2024, 4, 29);
+        // 30.04 и 01.05 нерабочие: 02.05, 03.05, 06.05
+        let arrival = data.get_arrival_date("WD", &order_date).unwrap();
+        assert_eq!(arrival, ymd(2024, 5, 6));
+        assert_eq!(Period::Week.start(&arrival), ymd(2024, 5, 6));
+        assert_eq!(Period::Month.start(&arrival), ymd(2024, 5, 1));
+        // календарные дни тоже не считают нерабочие дни компании: 02.05, 03.05, 04.05
+        assert_eq!(data.get_arrival_date("D", &order_date), Ok(ymd(2024, 5, 4)));
+        // у поставщика "X" нерабочий еще и 02.05: 03.05, 06.05, 07.05
+        assert_eq!(data.get_arrival_date("S", &order_date), Ok(ymd(2024, 5, 7)));
+    }
+
+    #[test]
+    fn order_by_date_is_latest_date_that_arrives_in_time() {
+        let data = lead_time_data();
+        for material in ["W", "WD", "D", "S"] {
+            for day in 1..=31 {
+                let need = ymd(2024, 5, day);
+                let order_by = data.get_order_by_date(material, &need).unwrap();
+                assert!(data.get_arrival_date(material, &order_by).unwrap() <= need, "{} {}", material, need);
+                let next_day = order_by.succ_opt().unwrap();
+                assert!(data.get_arrival_date(material, &next_day).unwrap() > need, "{} {}", material, need);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_material() {
+        assert!(lead_time_data().get_arrival_date("?", &ymd(2024, 4, 1)).is_err());
+    }
 }
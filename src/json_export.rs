@@ -0,0 +1,160 @@
+//! Выгрузка прогноза в JSON.
+//!
+//! Схема (версия 1), количества - числа, даты - строки `ГГГГ-ММ-ДД`:
+//!
+//! ```text
+//! {
+//!   "schema_version": 1,
+//!   "generated_at": "2024-04-15T10:30:00",   // время расчета
+//!   "as_of_date": "2024-04-15",              // дата, от которой считается горизонт срока доставки
+//!   "period": "week",                        // day | week | month
+//!   "dates": ["2024-04-15", ...],            // начала интервалов (колонки отчета)
+//!   "materials": [
+//!     {
+//!       "name": "Резистор 910 0,25W (1206)",
+//!       "lead_time": {
+//!         "qty": 2,
+//!         "unit": "weeks",                   // weeks | days | working_days
+//!         "supplier": null,
+//!         "arrival_date": "2024-04-29",      // поступление при заказе в as_of_date
+//!         "horizon": "2024-04-29"            // начало первого интервала вне горизонта срока доставки
+//!       },
+//!       "first_shortage": "2024-05-06",      // null, если дефицита нет
+//!       "periods": [                         // по элементу на каждую дату из "dates", в том же порядке
+//!         {
+//!           "date": "2024-04-15",
+//!           "stock": 100,                    // остаток из файла остатков (посчитанное количество), 0 - нет инвентаризации
+//!           "stock_adjustment": 100,         // разница между посчитанным и ожидаемым по прогнозу остатком
+//!           "receipts": 0,                   // поступления по заказам поставщикам
+//!           "requirements": 40,              // потребность по плану обеспечения (положительное число)
+//!           "balance": 60,                   // накопленный остаток: предыдущий + stock_adjustment + receipts - requirements
+//!           "inside_lead_time_horizon": true,
+//!           "negative": false
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Поля могут добавляться без смены версии, изменение или удаление существующих полей меняет schema_version.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use chrono::{Local, NaiveDate, NaiveDateTime, Timelike, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::Zero;
+use serde::Serialize;
+use crate::init_data::InitialData;
+use crate::orders_plan::{append_qty, MaterialInfo};
+use crate::projection::Projection;
+use crate::report::get_horizon;
+use crate::settings::Settings;
+use crate::stock_count::{calculate_stock_counts, stock_movements};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub struct JsonProjection {
+    pub schema_version: u32,
+    pub generated_at: NaiveDateTime,
+    pub as_of_date: NaiveDate,
+    pub period: &'static str,
+    pub dates: Vec<NaiveDate>,
+    pub materials: Vec<JsonMaterial>
+}
+
+#[derive(Serialize)]
+pub struct JsonMaterial {
+    pub name: String,
+    pub lead_time: JsonLeadTime,
+    pub first_shortage: Option<NaiveDate>,
+    pub periods: Vec<JsonPeriod>
+}
+
+#[derive(Serialize)]
+pub struct JsonLeadTime {
+    pub qty: u32,
+    pub unit: &'static str,
+    pub supplier: Option<String>,
+    pub arrival_date: NaiveDate,
+    pub horizon: NaiveDate
+}
+
+#[derive(Serialize)]
+pub struct JsonPeriod {
+    pub date: NaiveDate,
+    pub stock: Decimal,
+    pub stock_adjustment: Decimal,
+    pub receipts: Decimal,
+    pub requirements: Decimal,
+    pub balance: Decimal,
+    pub inside_lead_time_horizon: bool,
+    pub negative: bool
+}
+
+pub fn build_json_projection(projection: &Projection, requirements: &Vec<MaterialInfo>, init_data: &InitialData, settings: &Settings) -> Result<JsonProjection, String> {
+    let now = Local::now().naive_local().with_nanosecond(0).unwrap();
+    let today = Utc::now().naive_utc().date();
+
+    let stock_counts = calculate_stock_counts(requirements, init_data);
+    let stocks_map: HashMap<_, _> = stock_counts.iter().map(|c| ((c.date, c.material), c.counted)).collect();
+    let stock_movements = stock_movements(&stock_counts);
+    let mut adjustments_map = HashMap::new();
+    append_qty(&mut adjustments_map, &stock_movements);
+    let mut receipts_map = HashMap::new();
+    for po in &init_data.purchase_orders {
+        append_qty(&mut receipts_map, &po.items);
+    }
+    let mut requirements_map = HashMap::new();
+    append_qty(&mut requirements_map, requirements);
+
+    let mut materials = vec![];
+    for (row, material) in projection.materials.iter().enumerate() {
+        let dt = init_data.get_delivery_time(material)?;
+        let horizon = get_horizon(init_data, material, &today, settings)?;
+        let mut periods = vec![];
+        for (col, date) in projection.dates.iter().enumerate() {
+            let key = (*date, material);
+            let balance = projection.balances[row][col];
+            periods.push(JsonPeriod {
+                date: *date,
+                stock: stocks_map.get(&key).copied().unwrap_or_default(),
+                stock_adjustment: adjustments_map.get(&key).copied().unwrap_or_default(),
+                receipts: receipts_map.get(&key).copied().unwrap_or_default(),
+                requirements: requirements_map.get(&key).map(|r| -r).unwrap_or_default(),
+                balance,
+                inside_lead_time_horizon: *date < horizon,
+                negative: balance < Decimal::zero(),
+            });
+        }
+        materials.push(JsonMaterial {
+            name: material.clone(),
+            lead_time: JsonLeadTime {
+                qty: dt.qty,
+                unit: dt.unit.name(),
+                supplier: dt.supplier.clone(),
+                arrival_date: init_data.get_arrival_date(material, &today)?,
+                horizon,
+            },
+            first_shortage: projection.first_shortage(material),
+            periods,
+        });
+    }
+
+    Ok(JsonProjection {
+        schema_version: SCHEMA_VERSION,
+        generated_at: now,
+        as_of_date: today,
+        period: settings.period.name(),
+        dates: projection.dates.clone(),
+        materials,
+    })
+}
+
+pub fn save_json(path: &str, projection: &Projection, requirements: &Vec<MaterialInfo>, init_data: &InitialData, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let json = build_json_projection(projection, requirements, init_data, settings)?;
+    fs::write(path, serde_json::to_string_pretty(&json)?)?;
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_xlsxwriter::{Color, Worksheet};
+use crate::init_data::InitialData;
+use crate::settings::Settings;
+use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsMatrix};
+
+/// Строка заказа, поступление по которой ожидается раньше, чем позволяет срок доставки материала
+#[derive(Debug)]
+pub struct EarlyReceipt {
+    pub order: String,
+    pub material: String,
+    pub order_date: NaiveDate,
+    /// Дата поступления по заказу
+    pub date: NaiveDate,
+    /// Самая ранняя дата поступления при заказе в order_date
+    pub earliest: NaiveDate,
+    pub weeks_early: i64
+}
+
+/// Проверка строк заказов с датой заказа в заголовке по срокам доставки.
+/// Вызывается до переноса просроченных поступлений, чтобы сравнивались даты из файлов заказов.
+/// Материалы без срока доставки пропускаются, ошибка по ним выдается при расчете
+pub fn check_purchase_orders(init_data: &InitialData, settings: &Settings) -> Vec<EarlyReceipt> {
+    let mut result = vec![];
+    for po in init_data.purchase_orders.iter() {
+        let Some(order_date) = po.order_date else {
+            continue;
+        };
+        for mi in po.items.iter() {
+            let Ok(arrival) = init_data.get_arrival_date(&mi.material, &order_date) else {
+                continue;
+            };
+            let earliest = settings.period.start(&arrival);
+            if mi.date < earliest {
+                result.push(EarlyReceipt {
+                    order: po.name.clone(),
+                    material: mi.material.clone(),
+                    order_date,
+                    date: mi.date,
+                    earliest,
+                    weeks_early: ((earliest - mi.date).num_days() + 6) / 7,
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Лист "Проверка сроков заказов": строки заказов, которые не могут поступить к указанной дате
+pub fn write_to_worksheet(sheet: &mut Worksheet, early: &[EarlyReceipt], settings: &Settings) {
+    let num_format = settings.period.num_format();
+    let rows = early.iter().map(|receipt| vec![
+        XlsCell::text(&receipt.order),
+        XlsCell::text(&receipt.material),
+        XlsCell::date(receipt.order_date, num_format),
+        XlsCell::date(receipt.date, num_format),
+        XlsCell::date(receipt.earliest, num_format),
+        XlsCell::decimal(Decimal::from(receipt.weeks_early)).with_format(XlsCellFormat::FontColor(Color::Red)),
+    ]).collect();
+    let matrix = XlsMatrix::table(&["Заказ", "Материал", "Дата заказа", "Дата поступления", "Не раньше", "Раньше срока, нед."], rows, 0);
+    matrix.write_to_worksheet(sheet);
+}
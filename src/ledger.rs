@@ -0,0 +1,116 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{ToPrimitive, Zero};
+use rust_xlsxwriter::{ColNum, Format, RowNum, Table, TableColumn, Worksheet, XlsxError};
+use crate::init_data::InitialData;
+use crate::orders_plan::{calculate_need_for_materials, find_specification};
+use crate::stock_count::calculate_stock_counts;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MovementType {
+    Stock,
+    Receipt,
+    Requirement
+}
+
+impl MovementType {
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MovementType::Stock => "Остаток",
+            MovementType::Receipt => "Поступление",
+            MovementType::Requirement => "Потребность"
+        }
+    }
+}
+
+/// Строка журнала движений: одно движение материала в интервале с накопленным остатком после него
+pub struct LedgerEntry {
+    pub material: String,
+    pub date: NaiveDate,
+    pub movement: MovementType,
+    pub document: String,
+    pub qty: Decimal,
+    pub balance: Decimal
+}
+
+const HEADERS: [&str; 6] = ["Материал", "Дата", "Движение", "Документ", "Количество", "Остаток"];
+
+/// Журнал движений по материалам: остатки, строки заказов поставщикам и строки плана обеспечения,
+/// отсортированный по материалу, дате и виду движения
+pub fn build_ledger(init_data: &InitialData) -> Result<Vec<LedgerEntry>, String> {
+    let mut entries = vec![];
+    let mut push = |material: &String, date: NaiveDate, movement: MovementType, document: String, qty: Decimal| {
+        if !qty.is_zero() {
+            entries.push(LedgerEntry { material: material.clone(), date, movement, document, qty, balance: Decimal::zero() });
+        }
+    };
+
+    for count in calculate_stock_counts(&calculate_need_for_materials(init_data)?, init_data) {
+        push(count.material, count.date, MovementType::Stock, "Остатки".to_string(), count.adjustment());
+    }
+    for po in init_data.purchase_orders.iter() {
+        for mi in po.items.iter() {
+            push(&mi.material, mi.date, MovementType::Receipt, format!("Заказ {}", po.name), mi.qty);
+        }
+    }
+    for ppi in init_data.purchase_plan_items.iter() {
+        let sp = find_specification(ppi.product_name.clone(), &init_data.specifications)?;
+        for spi in sp.items.iter() {
+            push(&spi.material_name, ppi.date, MovementType::Requirement, format!("План: {}", ppi.product_name), -ppi.qty * spi.qty);
+        }
+    }
+
+    entries.sort_by(|a, b| (&a.material, a.date, a.movement, &a.document).cmp(&(&b.material, b.date, b.movement, &b.document)));
+    let mut balance = Decimal::zero();
+    for i in 0..entries.len() {
+        if i == 0 || entries[i - 1].material != entries[i].material {
+            balance = Decimal::zero();
+        }
+        balance += entries[i].qty;
+        entries[i].balance = balance;
+    }
+    Ok(entries)
+}
+
+/// CSV в формате исходных данных: разделитель ";", десятичная запятая, UTF-8 с BOM
+pub fn save_csv(path: &str, entries: &[LedgerEntry]) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(path)?;
+    file.write_all(b"\xEF\xBB\xBF")?;
+    let mut writer = csv::WriterBuilder::new().delimiter(b';').from_writer(file);
+    writer.write_record(HEADERS)?;
+    for e in entries {
+        writer.write_record([
+            e.material.clone(),
+            e.date.format("%d.%m.%Y").to_string(),
+            e.movement.name().to_string(),
+            e.document.clone(),
+            e.qty.normalize().to_string().replace('.', ","),
+            e.balance.normalize().to_string().replace('.', ","),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Лист в виде таблицы Excel с автофильтром, для сводных таблиц
+pub fn write_to_worksheet(sheet: &mut Worksheet, entries: &[LedgerEntry]) -> Result<(), XlsxError> {
+    let date_format = Format::new().set_num_format("dd.mm.yyyy");
+    for (i, e) in entries.iter().enumerate() {
+        let row = (i + 1) as RowNum;
+        sheet.write(row, 0, &e.material)?;
+        sheet.write_with_format(row, 1, &e.date, &date_format)?;
+        sheet.write(row, 2, e.movement.name())?;
+        sheet.write(row, 3, &e.document)?;
+        sheet.write(row, 4, e.qty.to_f64())?;
+        sheet.write(row, 5, e.balance.to_f64())?;
+    }
+    let columns: Vec<TableColumn> = HEADERS.iter().map(|h| TableColumn::new().set_header(*h)).collect();
+    let table = Table::new().set_name("Движения").set_columns(&columns).set_autofilter(true);
+    sheet.add_table(0, 0, entries.len().max(1) as RowNum, (HEADERS.len() - 1) as ColNum, &table)?;
+    sheet.autofit();
+    Ok(())
+}
@@ -1,53 +1,61 @@
-
+mod audit;
+mod calendar;
+mod charts;
+mod csv_table;
+mod feasibility;
+mod formula_report;
+mod history;
+mod html_report;
 mod init_data;
+mod json_export;
+mod lead_time_check;
+mod ledger;
+mod order_files;
 mod orders_plan;
+mod overdue;
+mod period;
+mod projection;
+mod promise;
+mod report;
+mod report_diff;
+mod scenario;
+mod server;
+mod settings;
+mod stock_count;
+mod watch;
 mod xls_matrix;
 mod xlsx;
 
 use std::error::Error;
-use std::{env};
+use std::{env, fs};
 use std::collections::HashMap;
-use std::slice::SliceIndex;
-use calamine::{DataType, Reader};
-use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
+use std::str::FromStr;
+use std::path::Path;
+use chrono::{Local, NaiveDate};
 use rust_decimal::Decimal;
-use rust_decimal::prelude::{FromPrimitive, ToPrimitive, Zero};
-use rust_xlsxwriter::{ColNum, Color, RowNum, Workbook};
-
-use crate::init_data::{InitialData, DeliveryTime, PurchaseOrder, PurchasePlanItem, Specification, SpecificationItem};
-use crate::orders_plan::{calculate_stocks_plan, calculate_need_for_materials, MaterialInfo};
-use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsCellValue, XlsMatrix};
+use rust_decimal::prelude::ToPrimitive;
+use rust_xlsxwriter::{ColNum, RowNum, Workbook};
+
+use crate::feasibility::Coverage;
+use crate::init_data::{InitialData, OrderStatus};
+use crate::orders_plan::{calculate_stocks_plan, calculate_need_for_materials};
+use crate::projection::Projection;
+use crate::report::build_matrix;
+use crate::report_diff::ReportDiff;
+use crate::settings::{DEFAULT_HISTORY_DB, OutputFormat, Settings};
 use crate::xlsx::*;
 
 
-#[inline]
-fn check_date(date: &NaiveDate, file_name: &String) {
-    if date.weekday() != Weekday::Mon {
-        panic!("{} не понедельник, в файле: {}",date, file_name)
+fn get_output_path(file_name: &str) -> String {
+    if Path::new(file_name).is_absolute() {
+        return file_name.to_string();
     }
+    format!("{}/{}",env::current_exe().unwrap().parent().unwrap().to_str().unwrap(), file_name)
 }
 
-#[inline]
-fn collect_dates_and_materials(stocks_plan: &HashMap<(NaiveDate, &String), Decimal>) -> (Vec<NaiveDate>, Vec<String>) {
-    let mut dates = vec![];
-    let mut materials = vec![];
-
-    for item in stocks_plan.iter() {
-        if !dates.contains(&item.0.0) {
-            dates.push(item.0.0);
-        }
-        if !materials.contains(item.0.1) {
-            materials.push(item.0.1.clone());
-        }
-    }
-    dates.sort();
-    materials.sort();
-    (dates, materials)
-}
-
-fn read_sp_material_names() -> Vec<String> {
+fn read_sp_material_names(settings: &Settings) -> Vec<String> {
     let mut items = vec![];
-    for sp in read_specifications() {
+    for sp in read_specifications(settings) {
         for spi in sp.items {
             if !items.contains(&spi.material_name) {
                 items.push(spi.material_name.clone())
@@ -57,27 +65,25 @@ fn read_sp_material_names() -> Vec<String> {
     items
 }
 
-fn correct_stocks_file()-> Result<(), Box<dyn Error>> {
-    let stocks = read_stocks();
+fn correct_stocks_file(settings: &Settings)-> Result<(), Box<dyn Error>> {
+    let stocks = read_stocks(settings);
 
-    let mut result: HashMap<String, Decimal> = HashMap::new();
+    // остатки в файле - инвентаризации, берется последняя по дате (при равной дате - последняя в файле)
+    let mut latest: HashMap<String, (NaiveDate, Decimal)> = HashMap::new();
 
-    let names = read_sp_material_names();
+    let names = read_sp_material_names(settings);
     for mn in names {
         for smi in stocks.iter() {
-            if mn.trim() == smi.material.trim() {
-                let mut value = Decimal::zero();
-                if result.contains_key(&mn) {
-                    value = result.get(&mn).unwrap().clone();// value + smi.qty;
-                }
-                result.insert(smi.material.clone(), value+smi.qty);
+            if mn.trim() == smi.material.trim() && latest.get(&smi.material).is_none_or(|(date, _)| *date <= smi.date) {
+                latest.insert(smi.material.clone(), (smi.date, smi.qty));
             }
         }
     }
+    let result: HashMap<String, Decimal> = latest.into_iter().map(|(material, (_, qty))| (material, qty)).collect();
 
 
     let mut workbook = Workbook::new();
-    let mut _worksheet = workbook.add_worksheet().set_name("стр")?;
+    let _worksheet = workbook.add_worksheet().set_name("стр")?;
 
     for (i,(key, val)) in result.iter().enumerate() {
         _worksheet.write((i + 1) as RowNum, 1 as ColNum, key).expect("TODO: panic message");
@@ -88,135 +94,255 @@ fn correct_stocks_file()-> Result<(), Box<dyn Error>> {
 
     _worksheet.autofit();
 
-    workbook.save(format!("{}/{}",env::current_exe()?.parent().unwrap().to_str().unwrap(), "Остатки (кор.).xlsx"))?;
-    println!("Создание файла завершено, смотрите файл \"{}\"","Остатки (кор.).xlsx");
+    workbook.save(get_output_path("Остатки (кор.).xlsx"))?;
+    println!("Создание файла завершено, смотрите файл \"Остатки (кор.).xlsx\"");
 
 
 
     Ok(())
 }
 
-fn create_empty_stocks() -> Result<(), Box<dyn Error>>{
+fn create_empty_stocks(settings: &Settings) -> Result<(), Box<dyn Error>>{
     println!("Создание файла остатков");
 
-    let mut items = read_sp_material_names();
+    let items = read_sp_material_names(settings);
 
     let mut workbook = Workbook::new();
-    let mut _worksheet = workbook.add_worksheet().set_name("стр")?;
+    let _worksheet = workbook.add_worksheet().set_name("стр")?;
 
-    for i in 0..items.len(){
-        _worksheet.write((i + 1) as RowNum, 1 as ColNum, &items[i]).expect("TODO: panic message");
+    for (i, item) in items.iter().enumerate() {
+        _worksheet.write((i + 1) as RowNum, 1 as ColNum, item).expect("TODO: panic message");
 
     }
 
 
     _worksheet.autofit();
 
-    workbook.save(format!("{}/{}",env::current_exe()?.parent().unwrap().to_str().unwrap(), "Остатки (авто).xlsx"))?;
-    println!("Создание файла завершено, смотрите файл \"{}\"","Остатки (авто).xlsx");
+    workbook.save(get_output_path("Остатки (авто).xlsx"))?;
+    println!("Создание файла завершено, смотрите файл \"Остатки (авто).xlsx\"");
     Ok(())
 }
 
-fn  main() -> Result<(), Box<dyn Error>> {
-
-    for argument in env::args() {
-        if argument.eq("-correct_stocks") {
-            correct_stocks_file()?;
-            return Ok(());
-        }
-        if argument.eq("-empty_stocks") {
-            create_empty_stocks()?;
-            return Ok(())
+/// Сравнение прогноза с выбранным (или последним) расчетом из каталога "Архив"
+fn compare_with_archive(projection: &Projection, settings: &Settings) -> Result<Option<ReportDiff>, Box<dyn Error>> {
+    let archive_dir = get_output_path("Архив");
+
+    let previous_path = match &settings.compare {
+        Some(name) if Path::new(name).is_absolute() => Some(name.clone()),
+        Some(name) => Some(format!("{}/{}", archive_dir, name)),
+        None => {
+            let mut names: Vec<String> = fs::read_dir(&archive_dir).into_iter().flatten()
+                .map(|e| e.unwrap().file_name().to_str().unwrap().to_string())
+                .filter(|n| n.ends_with(".xlsx"))
+                .collect();
+            names.sort();
+            names.last().map(|n| format!("{}/{}", archive_dir, n))
         }
+    };
+
+    let Some(previous_path) = previous_path else {
+        return Ok(None);
+    };
+    if !Path::new(&previous_path).exists() {
+        return Err(format!("Не найден архивный расчет для сравнения: {}", previous_path).into());
     }
+    let previous_name = Path::new(&previous_path).file_name().unwrap().to_str().unwrap().to_string();
+    let previous = Projection::read_xlsx(previous_path);
+
+    let diff = ReportDiff::compare(&previous_name, &previous, projection);
+    println!("Изменения относительно \"{}\": новых дефицитов - {}, закрытых - {}, сдвинувшихся - {}, измененных ячеек - {}",
+             previous_name, diff.new_shortages.len(), diff.resolved_shortages.len(),
+             diff.moved_shortages.len(), diff.changed_cells.len());
+    Ok(Some(diff))
+}
 
-    println!("Расчет состояния заказов...");
+/// Сохраняет прогноз в каталог "Архив". Вызывается после сохранения отчетов,
+/// чтобы неудачный расчет не попадал в архив
+fn save_to_archive(projection: &Projection) -> Result<(), Box<dyn Error>> {
+    let archive_dir = get_output_path("Архив");
+    fs::create_dir_all(&archive_dir)?;
+    let run_name = format!("Состояние заказов_{}.xlsx", Local::now().format("%Y%m%d_%H%M%S"));
+    projection.save_xlsx(&format!("{}/{}", archive_dir, run_name))?;
+    Ok(())
+}
 
-    let init_data = InitialData {
-        purchase_orders: read_purchase_orders(),
-        delivery_times: read_delivery_time_items(),
-        specifications: read_specifications(),
-        stocks: read_stocks(),
-        purchase_plan_items: read_purchase_plan_items()
+fn read_initial_data(settings: &Settings) -> InitialData {
+    let mut purchase_orders = read_purchase_orders(settings);
+    let receipts = read_receipts(settings);
+    purchase_orders.iter_mut().for_each(|po| po.apply_receipts(&receipts));
+    let mut init_data = InitialData {
+        purchase_orders,
+        delivery_times: read_delivery_time_items(settings),
+        calendars: read_calendars(),
+        specifications: read_specifications(settings),
+        stocks: read_stocks(settings),
+        purchase_plan_items: read_purchase_plan_items(settings),
+        overdue_receipts: vec![],
+        early_receipts: vec![]
     };
+    init_data.early_receipts = lead_time_check::check_purchase_orders(&init_data, settings);
+    init_data.overdue_receipts = overdue::take_overdue_receipts(&mut init_data.purchase_orders, settings);
+    init_data
+}
 
-    let need_for_materials = calculate_need_for_materials(&init_data)?;
-    let stocks_plan = calculate_stocks_plan(&need_for_materials, &init_data);
-    let (dates, materials) = collect_dates_and_materials(&stocks_plan);
+/// Расчет и сохранение отчета "Состояние заказов" во всех выбранных форматах
+fn calculate_orders_state(settings: &Settings) -> Result<Projection, Box<dyn Error>> {
+    println!("Расчет состояния заказов...");
 
+    let init_data = read_initial_data(settings);
+    let priorities = if settings.allocation { read_priorities(settings)? } else { vec![] };
+    if init_data.purchase_orders.iter().any(|po| po.status != OrderStatus::Open) {
+        let count = |status: OrderStatus| init_data.purchase_orders.iter().filter(|po| po.status == status).count();
+        println!("Заказов поставщикам: открыто - {}, частично получено - {}, закрыто - {}, отменено - {}",
+                 count(OrderStatus::Open), count(OrderStatus::PartiallyReceived), count(OrderStatus::Closed), count(OrderStatus::Cancelled));
+    }
+    if !init_data.early_receipts.is_empty() {
+        println!("Строк заказов с поступлением раньше срока доставки: {}", init_data.early_receipts.len());
+    }
+    if !init_data.overdue_receipts.is_empty() {
+        println!("Просроченных поступлений по заказам: {}, {}", init_data.overdue_receipts.len(), overdue::mode_description(settings.overdue));
+    }
 
-    let mut matrix = XlsMatrix::new();
-    let mut dates_row: Vec<XlsCell> = Vec::new();
+    let need_for_materials = calculate_need_for_materials(&init_data)?;
+    let stocks_plan = calculate_stocks_plan(&need_for_materials, &init_data);
+    let projection = Projection::from_stocks_plan(&stocks_plan);
+    let mut matrix = build_matrix(&projection, &init_data, settings)?;
 
-    dates_row.push(XlsCell{ cell_value: XlsCellValue::None, formats: vec![] });
-    for date in dates.iter() {
-        dates_row.push(XlsCell{ cell_value: XlsCellValue::Date(date.clone()), formats: vec![] })
-    }
-    matrix.rows.push(dates_row);
-    for m in materials.iter() {
-        matrix.rows.push(vec![XlsCell{ cell_value: XlsCellValue::String(m.clone()), formats: vec![] }])
+    let diff = compare_with_archive(&projection, settings)?;
+    if let Some(diff) = &diff {
+        diff.mark_matrix(&mut matrix, &projection);
     }
 
-    for row_num in 0..materials.len() {
-        let mut cur_value = Decimal::zero();
+    let ledger_entries = if settings.ledger { ledger::build_ledger(&init_data)? } else { vec![] };
+    if settings.ledger {
+        ledger::save_csv(&get_output_path("Движения.csv"), &ledger_entries)?;
+        println!("Журнал движений сохранен в файл \"Движения.csv\"");
+    }
 
-        for col_num in 0..dates.len() {
-            let acc_val = stocks_plan.get(&(dates[col_num], &materials[row_num]));
+    if settings.formats.contains(&OutputFormat::Xlsx) {
+        let mut workbook = Workbook::new();
+        let _worksheet = workbook.add_worksheet().set_name("Состояние заказов")?;
+        if settings.formulas {
+            formula_report::write_to_worksheet(_worksheet, &projection, &need_for_materials, &init_data, diff.as_ref(), settings)?;
+        } else {
+            matrix.write_to_worksheet(_worksheet);
+        }
+        _worksheet.autofit();
 
-            if let Some(cell_val) = acc_val {
-                cur_value += cell_val;
-                matrix.rows[row_num+1].push(XlsCell{ cell_value: XlsCellValue::Decimal(cur_value), formats: vec![] });
-            } else {
-                matrix.rows[row_num+1].push(XlsCell{ cell_value: XlsCellValue::Decimal(cur_value), formats: vec![] });
-            }
+        if let Some(diff) = &diff {
+            let diff_sheet = workbook.add_worksheet().set_name("Изменения")?;
+            diff.write_to_worksheet(diff_sheet);
+            diff_sheet.autofit();
+        }
+        if settings.ledger {
+            ledger::write_to_worksheet(workbook.add_worksheet().set_name("Движения")?, &ledger_entries)?;
         }
+        if !init_data.early_receipts.is_empty() {
+            lead_time_check::write_to_worksheet(workbook.add_worksheet().set_name("Проверка сроков заказов")?, &init_data.early_receipts, settings);
+        }
+        if !init_data.overdue_receipts.is_empty() {
+            overdue::write_to_worksheet(workbook.add_worksheet().set_name("Просроченные поступления")?, &init_data.overdue_receipts, settings);
+        }
+        let stock_counts = stock_count::calculate_stock_counts(&need_for_materials, &init_data);
+        if stock_counts.iter().any(|c| c.expected.is_some()) {
+            stock_count::write_to_worksheet(workbook.add_worksheet().set_name("Сверка остатков")?, &stock_counts, settings.period.num_format());
+        }
+        if settings.feasibility {
+            let feasibility = feasibility::calculate_feasibility(&init_data)?;
+            let short = feasibility.iter().filter(|f| f.buildable < f.planned).count();
+            println!("Строк плана, которые нельзя выполнить полностью: {} из {}", short, feasibility.len());
+            feasibility::write_to_worksheet(workbook.add_worksheet().set_name("Выполнимость плана")?, &feasibility, settings, false);
+        }
+        if settings.allocation {
+            let allocation = feasibility::calculate_allocation(&init_data, &priorities)?;
+            let count = |coverage: Coverage| allocation.iter().filter(|f| f.coverage() == coverage).count();
+            println!("Строк плана: обеспечено полностью - {}, частично - {}, не обеспечено - {}",
+                     count(Coverage::Full), count(Coverage::Partial), count(Coverage::None));
+            feasibility::write_to_worksheet(workbook.add_worksheet().set_name("Распределение")?, &allocation, settings, true);
+        }
+        if settings.charts.is_some() {
+            charts::write_charts(&mut workbook, &projection, &init_data, settings)?;
+        }
+        if settings.audit {
+            audit::write_audit_sheets(&mut workbook, &init_data, settings)?;
+        }
+
+        workbook.save(get_output_path("Состояние заказов.xlsx"))?;
+        println!("Расчет завершен, смотрите файл \"Состояние заказов.xlsx\"");
+    }
+    if settings.formats.contains(&OutputFormat::Json) {
+        json_export::save_json(&get_output_path("Состояние заказов.json"), &projection, &need_for_materials, &init_data, settings)?;
+        println!("Расчет завершен, смотрите файл \"Состояние заказов.json\"");
+    }
+    if settings.formats.contains(&OutputFormat::Html) {
+        html_report::save_html(&get_output_path("Состояние заказов.html"), &matrix, &projection, &init_data, settings)?;
+        println!("Расчет завершен, смотрите файл \"Состояние заказов.html\"");
     }
 
-    for cell in &mut matrix.rows[0] {
-        cell.formats.push(XlsCellFormat::Bordered);
-        cell.formats.push(XlsCellFormat::NumFormat("dd.mm.yyyy"));
+    save_to_archive(&projection)?;
+    if let Some(db) = &settings.history_db {
+        let run_id = history::save_run(&get_output_path(db), &init_data, &projection, settings)?;
+        println!("Расчет сохранен в историю \"{}\" (запуск №{})", db, run_id);
     }
+    Ok(projection)
+}
 
-    let now = Utc::now().naive_utc().date();
-    let mut now_index = 0;
-    for d in 0..dates.len() {
-        if dates[d]<=now && dates[d]+Duration::days(7) > now {
-            now_index = d;
-            break;
-        }
-    };
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%d.%m.%Y")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d"))
+        .map_err(|_| format!("Не удалось разобрать дату '{}'", value))
+}
 
-    for row in &mut matrix.rows {
-        row[now_index].formats.push(XlsCellFormat::Background(Color::RGB(0xEEEEEE)))
-    }
-    for row_num in 0..matrix.rows.len() {
-        let row = &mut matrix.rows[row_num];
-        for col_num in 0..row.len() {
-            let mut cell = &mut row[col_num];
-            cell.formats.push(XlsCellFormat::Bordered);
-            match cell.cell_value {
-                XlsCellValue::Decimal(d) => {
-                    if(col_num<=now_index+init_data.get_delivery_weeks(&materials[row_num-1])?){
-                        if d<Decimal::zero() {
-                            cell.formats.push(XlsCellFormat::FontColor(Color::Red));
-                        } else {
-                            cell.formats.push(XlsCellFormat::FontColor(Color::Green));
-                        }
-                    } else {
-                        cell.formats.push(XlsCellFormat::FontColor(Color::RGB(0xDDDDDD)));
-                    }
-                }
-                _ => ()
-            }
+fn  main() -> Result<(), Box<dyn Error>> {
+
+    let args: Vec<String> = env::args().collect();
+    let settings = Settings::from_args(&args)?;
+
+    for argument in args.iter() {
+        if argument.eq("-correct_stocks") {
+            correct_stocks_file(&settings)?;
+            return Ok(());
+        }
+        if argument.eq("-empty_stocks") {
+            create_empty_stocks(&settings)?;
+            return Ok(())
+        }
+        if argument.eq("-history") {
+            let position = args.iter().position(|a| a == "-history").unwrap();
+            let (Some(material), Some(date)) = (args.get(position + 1), args.get(position + 2)) else {
+                return Err("Использование: -history <материал> <дата (дд.мм.гггг)>".into());
+            };
+            let date = parse_date(date)?;
+            let db = settings.history_db.clone().unwrap_or(DEFAULT_HISTORY_DB.to_string());
+            history::print_material_history(&get_output_path(&db), material, &settings.period.start(&date))?;
+            return Ok(())
+        }
+        if argument.eq("-promise") {
+            let position = args.iter().position(|a| a == "-promise").unwrap();
+            let (Some(product), Some(qty), Some(date)) = (args.get(position + 1), args.get(position + 2), args.get(position + 3)) else {
+                return Err("Использование: -promise <товар> <количество> <дата (дд.мм.гггг)>".into());
+            };
+            let qty = Decimal::from_str(&qty.replace(',', "."))
+                .map_err(|_| format!("Не удалось разобрать количество '{}'", qty))?;
+            promise::print_promise(product, qty, &parse_date(date)?, &read_initial_data(&settings), &settings)?;
+            return Ok(())
+        }
+        if argument.eq("-watch") {
+            return watch::watch(&settings);
+        }
+        if argument.eq("-scenarios") {
+            scenario::compare_scenarios(&get_output_path("Сценарии.xlsx"), &settings)?;
+            println!("Сравнение сценариев сохранено в файл \"Сценарии.xlsx\"");
+            return Ok(())
+        }
+        if argument.eq("-create_orders") {
+            return order_files::create_purchase_orders(&settings);
+        }
+        if argument.eq("-serve") {
+            return server::serve(&settings);
         }
     }
 
-    let mut workbook = Workbook::new();
-    let mut _worksheet = workbook.add_worksheet().set_name("Состояние заказов")?;
-    matrix.write_to_worksheet(&mut _worksheet);
-    _worksheet.autofit();
-
-    workbook.save(format!("{}/{}",env::current_exe()?.parent().unwrap().to_str().unwrap(), "Состояние заказов.xlsx"))?;
-    println!("Расчет завершен, смотрите файл \"{}\"","Состояние заказов.xlsx");
+    calculate_orders_state(&settings)?;
     Ok(())
 }
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use rust_xlsxwriter::{ColNum, Format, RowNum, Workbook};
+use crate::init_data::PurchaseOrder;
+use crate::orders_plan::MaterialInfo;
+use crate::settings::Settings;
+use crate::xlsx::{get_template_path, is_input_file, read_approved_proposals, read_delivery_time_items, read_purchase_orders};
+
+const ORDERS_DIR: &str = "Заказы поставщикам";
+const NO_SUPPLIER: &str = "не указан";
+
+/// Следующий свободный номер заказа: наибольший числовой номер из имен "Заказ_<номер>" плюс один
+fn next_order_number(dir: &str) -> Result<u32, Box<dyn Error>> {
+    let mut last = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path().into_os_string().to_str().unwrap().to_string();
+        if !is_input_file(&path) {
+            continue;
+        }
+        let stem = Path::new(&path).file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        if let Some(number) = stem.strip_prefix("Заказ_").and_then(|n| n.parse::<u32>().ok()) {
+            last = last.max(number);
+        }
+    }
+    Ok(last + 1)
+}
+
+/// Файл заказа в формате "Заказ_NNN.xlsx": строка дат поступления, строки "Поставщик:" и "Дата заказа:",
+/// далее материалы в колонке A и количества по датам
+fn write_order_file(path: &str, supplier: &str, order_date: NaiveDate, items: &[&MaterialInfo]) -> Result<(), Box<dyn Error>> {
+    let date_format = Format::new().set_num_format("dd.mm.yyyy");
+    let mut dates: Vec<NaiveDate> = items.iter().map(|mi| mi.date).collect();
+    dates.sort();
+    dates.dedup();
+    let mut materials: Vec<&String> = items.iter().map(|mi| &mi.material).collect();
+    materials.sort();
+    materials.dedup();
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet().set_name("Лист1")?;
+    sheet.write(0, 0, "Материал\\Дата поступления:")?;
+    for (i, date) in dates.iter().enumerate() {
+        sheet.write_with_format(0, (i + 1) as ColNum, date, &date_format)?;
+    }
+    sheet.write(1, 0, "Поставщик:")?;
+    sheet.write(1, 1, supplier)?;
+    sheet.write(2, 0, "Дата заказа:")?;
+    sheet.write_with_format(2, 1, &order_date, &date_format)?;
+    for (row, material) in materials.iter().enumerate() {
+        let row = (row + 3) as RowNum;
+        sheet.write(row, 0, *material)?;
+        for (i, date) in dates.iter().enumerate() {
+            let qty = items.iter().filter(|mi| mi.material == **material && mi.date == *date).map(|mi| mi.qty).sum::<Decimal>();
+            if !qty.is_zero() {
+                sheet.write(row, (i + 1) as ColNum, qty.to_f64())?;
+            }
+        }
+    }
+    sheet.autofit();
+    workbook.save(path)?;
+    Ok(())
+}
+
+/// Предложение уже заказано: в одном из файлов заказов есть строка того же материала
+/// с тем же количеством и поступлением в том же интервале. Защищает от повторного запуска команды
+fn is_ordered(proposal: &MaterialInfo, existing: &[PurchaseOrder], settings: &Settings) -> bool {
+    let date = settings.period.start(&proposal.date);
+    existing.iter().flat_map(|po| po.items.iter())
+        .any(|mi| mi.material == proposal.material && mi.date == date && mi.qty == proposal.qty)
+}
+
+/// Заказы поставщикам из утвержденных предложений: по файлу на поставщика в каталоге "Заказы поставщикам"
+/// со следующими свободными номерами. Поставщик материала берется из файла сроков доставки.
+/// Предложения, которые уже есть в файлах заказов, пропускаются
+pub fn create_purchase_orders(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    if settings.workbook.is_some() {
+        return Err(format!("Заказы создаются в каталоге \"{}\", со сводной книгой (-workbook) команда не работает", ORDERS_DIR).into());
+    }
+    let existing = read_purchase_orders(settings);
+    let (ordered, proposals): (Vec<_>, Vec<_>) = read_approved_proposals(settings)?.into_iter()
+        .partition(|mi| is_ordered(mi, &existing, settings));
+    if !ordered.is_empty() {
+        println!("Предложений, которые уже есть в заказах поставщикам: {} (пропущены)", ordered.len());
+    }
+    if proposals.is_empty() {
+        println!("Нет утвержденных предложений к заказу");
+        return Ok(());
+    }
+    let delivery_times = read_delivery_time_items(settings);
+    let supplier = |material: &String| delivery_times.iter()
+        .find(|dt| dt.material_name == *material)
+        .and_then(|dt| dt.supplier.clone())
+        .unwrap_or(NO_SUPPLIER.to_string());
+    let mut by_supplier: BTreeMap<String, Vec<&MaterialInfo>> = BTreeMap::new();
+    for mi in proposals.iter() {
+        by_supplier.entry(supplier(&mi.material)).or_default().push(mi);
+    }
+
+    let dir = get_template_path(ORDERS_DIR);
+    let today = Utc::now().naive_utc().date();
+    for (number, (supplier, items)) in (next_order_number(&dir)?..).zip(by_supplier.iter()) {
+        let file_name = format!("Заказ_{}.xlsx", number);
+        write_order_file(&format!("{}/{}", dir, file_name), supplier, today, items)?;
+        println!("Поставщик {}: создан файл \"{}/{}\", строк - {}", supplier, ORDERS_DIR, file_name, items.len());
+    }
+    Ok(())
+}
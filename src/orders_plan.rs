@@ -2,10 +2,11 @@ use std::collections::HashMap;
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use crate::init_data::{InitialData, Specification};
+use crate::stock_count::calculate_stock_counts;
 
-fn find_specification(product_name: String, specifications: &Vec<Specification>) -> Result<&Specification,String> {
+pub fn find_specification(product_name: String, specifications: &Vec<Specification>) -> Result<&Specification,String> {
     for sp in specifications {
-        if sp.product_name == product_name { return Ok(&sp) }
+        if sp.product_name == product_name { return Ok(sp) }
     }
     Err(format!("Спецификация для товара \"{}\" не найдена.", product_name))
 }
@@ -16,7 +17,7 @@ pub fn calculate_need_for_materials(data_set: &InitialData) -> Result<Vec<Materi
     for ppi in &data_set.purchase_plan_items {
         let sp = find_specification(ppi.product_name.clone(),&data_set.specifications)?;
         for spi in &sp.items {
-            let tuple = (ppi.date.clone(),spi.material_name.clone());
+            let tuple = (ppi.date,spi.material_name.clone());
             if !map.contains_key(&tuple){
                 map.insert(tuple, Decimal::from(-1)*ppi.qty*spi.qty);
             }
@@ -29,9 +30,9 @@ pub fn calculate_need_for_materials(data_set: &InitialData) -> Result<Vec<Materi
     let mut result = vec![];
     for hmv in map.iter() {
         result.push(MaterialInfo {
-            date: hmv.0.0.clone(),
+            date: hmv.0.0,
             material: hmv.0.1.clone(),
-            qty: hmv.1.clone(),
+            qty: *hmv.1,
         })
     }
     Ok(result)
@@ -41,7 +42,7 @@ pub fn append_qty<'a, 'b, 'c>(map: &'a mut HashMap<(NaiveDate, &'c String),Decim
 where 'b: 'a, 'b: 'c
 {
     for mi in mis {
-        let tuple = (mi.date.clone(),&mi.material);
+        let tuple = (mi.date,&mi.material);
         if !map.contains_key(&tuple){
             let ir = map.insert(tuple, mi.qty);
             if ir.is_some() {panic!("ir is some")}
@@ -56,8 +57,10 @@ where 'b: 'a, 'b: 'c
 
 pub fn calculate_stocks_plan<'a>(requirements: &'a Vec<MaterialInfo>, data_set: &'a InitialData) -> HashMap<(NaiveDate, &'a String), Decimal> {
     let mut map: HashMap<(NaiveDate, &String), Decimal> = HashMap::new();
-    append_qty(&mut map, &data_set.stocks);
-    append_qty(&mut map, &requirements);
+    for count in calculate_stock_counts(requirements, data_set) {
+        *map.entry((count.date, count.material)).or_default() += count.adjustment();
+    }
+    append_qty(&mut map, requirements);
     for po in &data_set.purchase_orders {
         append_qty(&mut map, &po.items)
     }
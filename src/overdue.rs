@@ -0,0 +1,77 @@
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use rust_xlsxwriter::{Color, Worksheet};
+use crate::init_data::PurchaseOrder;
+use crate::settings::{OverdueMode, Settings};
+use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsMatrix};
+
+/// Строка заказа поставщику с датой поступления раньше текущего интервала, поступление по которой не подтверждено
+#[derive(Debug)]
+pub struct OverdueReceipt {
+    pub order: String,
+    pub material: String,
+    /// Дата поступления по заказу
+    pub date: NaiveDate,
+    pub qty: Decimal,
+    pub weeks_late: i64
+}
+
+/// Просроченные строки заказов: в зависимости от settings.overdue они переносятся в текущий интервал,
+/// исключаются из прогноза остатка (показываются в колонке "Просрочено" отчета) или остаются на своих датах
+pub fn take_overdue_receipts(purchase_orders: &mut [PurchaseOrder], settings: &Settings) -> Vec<OverdueReceipt> {
+    let today = Utc::now().naive_utc().date();
+    let now = settings.period.start(&today);
+    let mut result = vec![];
+    for po in purchase_orders.iter_mut() {
+        for mi in po.items.iter().filter(|mi| mi.date < now) {
+            result.push(OverdueReceipt {
+                order: po.name.clone(),
+                material: mi.material.clone(),
+                date: mi.date,
+                qty: mi.qty,
+                weeks_late: (now - mi.date).num_days() / 7,
+            });
+        }
+        match settings.overdue {
+            OverdueMode::CurrentPeriod => po.items.iter_mut().filter(|mi| mi.date < now).for_each(|mi| mi.date = now),
+            OverdueMode::PastDue => po.items.retain(|mi| mi.date >= now),
+            OverdueMode::Keep => {}
+        }
+    }
+    result
+}
+
+/// Как учтены просроченные поступления, для сообщения в консоли
+pub fn mode_description(mode: OverdueMode) -> &'static str {
+    match mode {
+        OverdueMode::CurrentPeriod => "перенесены в текущий интервал (-overdue=keep - на даты заказов, -overdue=past_due - отдельной колонкой)",
+        OverdueMode::PastDue => "не учтены в остатке, показаны в колонке \"Просрочено\"",
+        OverdueMode::Keep => "учтены на даты заказов"
+    }
+}
+
+/// Лист "Просроченные поступления": заказ, материал, количество и опоздание в неделях
+pub fn write_to_worksheet(sheet: &mut Worksheet, overdue: &[OverdueReceipt], settings: &Settings) {
+    let accounted = match settings.overdue {
+        OverdueMode::CurrentPeriod => "в текущем интервале",
+        OverdueMode::PastDue => "колонка \"Просрочено\"",
+        OverdueMode::Keep => "на дату заказа"
+    };
+    let num_format = settings.period.num_format();
+    let rows = overdue.iter().map(|receipt| {
+        let mut weeks_late = XlsCell::decimal(Decimal::from(receipt.weeks_late));
+        if receipt.weeks_late > 0 {
+            weeks_late = weeks_late.with_format(XlsCellFormat::FontColor(Color::Red));
+        }
+        vec![
+            XlsCell::text(&receipt.order),
+            XlsCell::text(&receipt.material),
+            XlsCell::date(receipt.date, num_format),
+            XlsCell::decimal(receipt.qty),
+            weeks_late,
+            XlsCell::text(accounted),
+        ]
+    }).collect();
+    let matrix = XlsMatrix::table(&["Заказ", "Материал", "Дата поступления", "Количество", "Опоздание, нед.", "В расчете"], rows, 0);
+    matrix.write_to_worksheet(sheet);
+}
@@ -0,0 +1,65 @@
+use chrono::{Datelike, Days, Duration, Months, NaiveDate, Weekday};
+
+/// Размер интервала планирования (колонки отчета)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+    Month
+}
+
+impl Period {
+
+    pub fn parse(value: &str) -> Result<Period, String> {
+        match value.trim().to_lowercase().as_str() {
+            "day" | "день" => Ok(Period::Day),
+            "week" | "неделя" => Ok(Period::Week),
+            "month" | "месяц" => Ok(Period::Month),
+            _ => Err(format!("Неизвестный интервал планирования '{}' (допустимо: day, week, month)", value))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month"
+        }
+    }
+
+    /// Начало интервала, в который попадает дата
+    pub fn start(&self, date: &NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => *date,
+            Period::Week => *date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Period::Month => date.with_day(1).unwrap()
+        }
+    }
+
+    /// Начало следующего интервала
+    pub fn next(&self, date: &NaiveDate) -> NaiveDate {
+        let start = self.start(date);
+        match self {
+            Period::Day => start + Days::new(1),
+            Period::Week => start + Days::new(7),
+            Period::Month => start + Months::new(1)
+        }
+    }
+
+    /// Проверка даты из исходного файла. Для недельного планирования даты в файлах
+    /// должны быть понедельниками, для остальных интервалов дата приводится к началу интервала.
+    pub fn check_date(&self, date: &NaiveDate, file_name: &str) -> NaiveDate {
+        if *self == Period::Week && date.weekday() != Weekday::Mon {
+            panic!("{} не понедельник, в файле: {}", date, file_name)
+        }
+        self.start(date)
+    }
+
+    /// Числовой формат заголовка колонки
+    pub fn num_format(&self) -> &'static str {
+        match self {
+            Period::Month => "mm.yyyy",
+            _ => "dd.mm.yyyy"
+        }
+    }
+}
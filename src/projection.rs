@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use calamine::{DataType, Range, Data};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{ToPrimitive, Zero};
+use rust_xlsxwriter::{ColNum, Format, RowNum, Workbook, XlsxError};
+use crate::xlsx::{read_decimal, read_table};
+
+/// Прогноз остатков: накопленный остаток материала на каждую дату
+pub struct Projection {
+    pub dates: Vec<NaiveDate>,
+    pub materials: Vec<String>,
+    /// balances[номер материала][номер даты]
+    pub balances: Vec<Vec<Decimal>>
+}
+
+#[inline]
+fn collect_dates_and_materials(stocks_plan: &HashMap<(NaiveDate, &String), Decimal>) -> (Vec<NaiveDate>, Vec<String>) {
+    let mut dates = vec![];
+    let mut materials = vec![];
+
+    for item in stocks_plan.iter() {
+        if !dates.contains(&item.0.0) {
+            dates.push(item.0.0);
+        }
+        if !materials.contains(item.0.1) {
+            materials.push(item.0.1.clone());
+        }
+    }
+    dates.sort();
+    materials.sort();
+    (dates, materials)
+}
+
+impl Projection {
+
+    pub fn from_stocks_plan(stocks_plan: &HashMap<(NaiveDate, &String), Decimal>) -> Projection {
+        let (dates, materials) = collect_dates_and_materials(stocks_plan);
+        let mut balances = vec![];
+        for material in materials.iter() {
+            let mut cur_value = Decimal::zero();
+            let mut row = vec![];
+            for date in dates.iter() {
+                if let Some(cell_val) = stocks_plan.get(&(*date, material)) {
+                    cur_value += cell_val;
+                }
+                row.push(cur_value);
+            }
+            balances.push(row);
+        }
+        Projection { dates, materials, balances }
+    }
+
+    pub fn get(&self, material: &str, date: &NaiveDate) -> Option<Decimal> {
+        let row = self.materials.iter().position(|m| m == material)?;
+        let col = self.dates.iter().position(|d| d == date)?;
+        Some(self.balances[row][col])
+    }
+
+    /// Первая дата, на которую остаток материала становится отрицательным
+    pub fn first_shortage(&self, material: &str) -> Option<NaiveDate> {
+        let row = self.materials.iter().position(|m| m == material)?;
+        self.balances[row].iter().position(|b| *b < Decimal::zero()).map(|col| self.dates[col])
+    }
+
+    /// Сохранение в простом виде (без оформления) для архива расчетов
+    pub fn save_xlsx(&self, path: &str) -> Result<(), XlsxError> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet().set_name("Прогноз")?;
+        let date_format = Format::new().set_num_format("dd.mm.yyyy");
+        worksheet.write(0, 0, "Материал\\Дата")?;
+        for (col, date) in self.dates.iter().enumerate() {
+            worksheet.write_with_format(0, (col + 1) as ColNum, date, &date_format)?;
+        }
+        for (row, material) in self.materials.iter().enumerate() {
+            worksheet.write((row + 1) as RowNum, 0, material)?;
+            for (col, balance) in self.balances[row].iter().enumerate() {
+                worksheet.write((row + 1) as RowNum, (col + 1) as ColNum, balance.to_f64())?;
+            }
+        }
+        workbook.save(path)
+    }
+
+    pub fn read_xlsx(path: String) -> Projection {
+        let mut projection = Projection { dates: vec![], materials: vec![], balances: vec![] };
+        read_table(path, None, |row, col, range: &Range<Data>| {
+            if row == 0 {
+                projection.dates.push(range.get((0usize, col)).unwrap().as_date().unwrap());
+                return;
+            }
+            if col == 1 {
+                projection.materials.push(range.get((row, 0usize)).unwrap().as_string().unwrap());
+                projection.balances.push(vec![]);
+            }
+            let balance = range.get((row, col)).and_then(read_decimal).unwrap_or_default();
+            projection.balances.last_mut().unwrap().push(balance);
+        }, |range| {
+            (0, range.height(), 1, range.width())
+        });
+        projection
+    }
+}
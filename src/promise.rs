@@ -0,0 +1,91 @@
+use std::error::Error;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use crate::init_data::InitialData;
+use crate::orders_plan::{calculate_need_for_materials, calculate_stocks_plan, find_specification};
+use crate::projection::Projection;
+use crate::report::get_horizon;
+use crate::settings::Settings;
+
+/// Возможность поставки по одному материалу спецификации
+struct MaterialPromise {
+    material: String,
+    need: Decimal,
+    /// Сколько материала свободно на желаемую дату
+    available: Decimal,
+    /// Первая дата не раньше желаемой, с которой материала хватает из остатков и заказов
+    atp: Option<NaiveDate>,
+    /// Начало первого интервала, в который поступит материал, если заказать его сегодня
+    horizon: NaiveDate
+}
+
+/// Свободный остаток на дату date: минимальный накопленный остаток на эту дату и все последующие,
+/// новая потребность уменьшает их все
+fn free_balance(projection: &Projection, row: usize, date: &NaiveDate) -> Decimal {
+    let balances = &projection.balances[row];
+    let before = projection.dates.iter().rposition(|d| d <= date);
+    let mut result = before.map(|col| balances[col]).unwrap_or_default();
+    for (col, d) in projection.dates.iter().enumerate() {
+        if d > date {
+            result = result.min(balances[col]);
+        }
+    }
+    result
+}
+
+fn material_promise(projection: &Projection, init_data: &InitialData, material: &str, need: Decimal,
+                    date: &NaiveDate, today: &NaiveDate, settings: &Settings) -> Result<MaterialPromise, String> {
+    let horizon = get_horizon(init_data, material, today, settings)?;
+    let (available, atp) = match projection.materials.iter().position(|m| m == material) {
+        Some(row) => {
+            let candidates = std::iter::once(*date).chain(projection.dates.iter().filter(|d| *d > date).copied());
+            let atp = candidates.into_iter().find(|d| free_balance(projection, row, d) >= need);
+            (free_balance(projection, row, date), atp)
+        }
+        None => (Decimal::ZERO, None)
+    };
+    Ok(MaterialPromise { material: material.to_string(), need, available, atp, horizon })
+}
+
+/// Ответ на запрос "можно ли поставить qty товара product к дате date":
+/// из имеющихся остатков и заказов (ATP) и с учетом новых заказов поставщикам, размещенных сегодня (CTP)
+pub fn print_promise(product: &str, qty: Decimal, date: &NaiveDate, init_data: &InitialData, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let today = Utc::now().naive_utc().date();
+    let date = settings.period.start(date);
+    let sp = find_specification(product.to_string(), &init_data.specifications)?;
+    let need_for_materials = calculate_need_for_materials(init_data)?;
+    let projection = Projection::from_stocks_plan(&calculate_stocks_plan(&need_for_materials, init_data));
+
+    let mut promises = vec![];
+    for spi in sp.items.iter().filter(|spi| spi.qty > Decimal::ZERO) {
+        promises.push(material_promise(&projection, init_data, &spi.material_name, qty * spi.qty, &date, &today, settings)?);
+    }
+
+    let format = |d: &NaiveDate| d.format("%d.%m.%Y").to_string();
+    println!("{} {} шт. к {}:", product, qty.normalize(), format(&date));
+    let atp = promises.iter().map(|p| p.atp).collect::<Option<Vec<_>>>().map(|dates| dates.into_iter().max().unwrap_or(date).max(date));
+    match atp {
+        Some(atp) if atp == date => println!("  из остатков и заказов (ATP): можно к желаемой дате"),
+        Some(atp) => println!("  из остатков и заказов (ATP): не раньше {}", format(&atp)),
+        None => println!("  из остатков и заказов (ATP): нельзя, материалов не хватает до конца расчета")
+    }
+    let ctp = promises.iter()
+        .map(|p| p.atp.map_or(p.horizon, |atp| atp.min(p.horizon)))
+        .max()
+        .unwrap_or(date)
+        .max(date);
+    if atp != Some(ctp) {
+        println!("  с новыми заказами поставщикам сегодня (CTP): не раньше {}", format(&ctp));
+    }
+
+    let short: Vec<_> = promises.iter().filter(|p| p.atp != Some(date)).collect();
+    if !short.is_empty() {
+        println!("  не хватает к желаемой дате:");
+    }
+    for p in short {
+        println!("    {}: нужно {}, свободно {}, из остатков и заказов - {}, при заказе сегодня - {}",
+                 p.material, p.need.normalize(), p.available.normalize(),
+                 p.atp.map_or("нет".to_string(), |d| format(&d)), format(&p.horizon));
+    }
+    Ok(())
+}
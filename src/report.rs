@@ -0,0 +1,139 @@
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::Zero;
+use rust_xlsxwriter::Color;
+use serde::Serialize;
+use crate::init_data::InitialData;
+use crate::projection::Projection;
+use crate::settings::{OverdueMode, Settings};
+use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsCellValue, XlsMatrix, XlsSheetOption};
+
+/// Начало первого интервала, в который может поступить материал, если заказать его сегодня.
+/// Интервалы до него - внутри горизонта срока доставки: дефицит в них новым заказом уже не закрыть.
+pub fn get_horizon(init_data: &InitialData, material: &str, today: &NaiveDate, settings: &Settings) -> Result<NaiveDate, String> {
+    let arrival = init_data.get_arrival_date(material, today)?;
+    Ok(settings.period.start(&arrival))
+}
+
+/// Колонка просроченных поступлений в режиме -overdue=past_due
+pub(crate) const PAST_DUE_HEADER: &str = "Просрочено";
+
+/// Строка сводки дефицитов
+#[derive(Serialize)]
+pub struct ShortageSummary {
+    pub material: String,
+    pub first_shortage: NaiveDate,
+    pub min_balance: Decimal,
+    pub inside_lead_time_horizon: bool,
+    pub lead_time_days: u32
+}
+
+/// Материалы, у которых прогнозный остаток уходит в минус
+pub fn collect_shortages(projection: &Projection, init_data: &InitialData, settings: &Settings) -> Result<Vec<ShortageSummary>, String> {
+    let today = Utc::now().naive_utc().date();
+    let mut result = vec![];
+    for (row, material) in projection.materials.iter().enumerate() {
+        let Some(first_shortage) = projection.first_shortage(material) else {
+            continue;
+        };
+        result.push(ShortageSummary {
+            material: material.clone(),
+            first_shortage,
+            min_balance: projection.balances[row].iter().min().copied().unwrap_or(Decimal::zero()),
+            inside_lead_time_horizon: first_shortage < get_horizon(init_data, material, &today, settings)?,
+            lead_time_days: init_data.get_delivery_time(material)?.days(),
+        });
+    }
+    Ok(result)
+}
+
+/// Матрица отчета "Состояние заказов": строка дат, далее по строке на материал.
+/// Первая колонка матрицы - наименования материалов, поэтому индекс колонки даты на единицу больше.
+pub fn build_matrix(projection: &Projection, init_data: &InitialData, settings: &Settings) -> Result<XlsMatrix, String> {
+    let dates = &projection.dates;
+    let materials = &projection.materials;
+
+    let mut matrix = XlsMatrix::new();
+    let mut dates_row: Vec<XlsCell> = Vec::new();
+
+    dates_row.push(XlsCell{ cell_value: XlsCellValue::None, formats: vec![] });
+    for date in dates.iter() {
+        dates_row.push(XlsCell{ cell_value: XlsCellValue::Date(*date), formats: vec![] })
+    }
+    matrix.rows.push(dates_row);
+    for (row_num, m) in materials.iter().enumerate() {
+        let mut row = vec![XlsCell{ cell_value: XlsCellValue::String(m.clone()), formats: vec![] }];
+        for balance in projection.balances[row_num].iter() {
+            row.push(XlsCell{ cell_value: XlsCellValue::Decimal(*balance), formats: vec![] });
+        }
+        matrix.rows.push(row);
+    }
+
+    for cell in &mut matrix.rows[0] {
+        cell.formats.push(XlsCellFormat::Bordered);
+        cell.formats.push(XlsCellFormat::NumFormat(settings.period.num_format()));
+    }
+
+    let today = Utc::now().naive_utc().date();
+    let now = settings.period.start(&today);
+    let now_col = dates.iter().position(|d| *d == now).map(|d| d + 1);
+
+    if let Some(now_col) = now_col {
+        for row in &mut matrix.rows {
+            row[now_col].formats.push(XlsCellFormat::Background(Color::RGB(0xEEEEEE)))
+        }
+    }
+    for (row_num, row) in matrix.rows.iter_mut().enumerate() {
+        let mut horizon: Option<NaiveDate> = None;
+        if row_num > 0 {
+            horizon = Some(get_horizon(init_data, &materials[row_num-1], &today, settings)?);
+        }
+        for (col_num, cell) in row.iter_mut().enumerate() {
+            cell.formats.push(XlsCellFormat::Bordered);
+            if let XlsCellValue::Decimal(d) = cell.cell_value {
+                let date = dates[col_num-1];
+                if date < horizon.unwrap() {
+                    if d<Decimal::zero() {
+                        cell.formats.push(XlsCellFormat::FontColor(Color::Red));
+                    } else {
+                        cell.formats.push(XlsCellFormat::FontColor(Color::Green));
+                    }
+                } else if d<Decimal::zero() && init_data.get_order_by_date(&materials[row_num-1], &date)? < settings.period.next(&today) {
+                    // дефицит еще можно закрыть, но заказ нужно разместить в текущем интервале
+                    cell.formats.push(XlsCellFormat::FontColor(Color::Orange));
+                } else {
+                    cell.formats.push(XlsCellFormat::FontColor(Color::RGB(0xDDDDDD)));
+                }
+            }
+        }
+    }
+
+    // просроченные поступления, не учтенные в остатке, - отдельной колонкой после дат
+    if settings.overdue == OverdueMode::PastDue && !init_data.overdue_receipts.is_empty() {
+        let past_due_format = |qty: Decimal| {
+            let mut formats = vec![XlsCellFormat::Bordered];
+            if qty > Decimal::zero() {
+                formats.push(XlsCellFormat::Background(Color::RGB(0xFFC7CE)));
+            }
+            formats
+        };
+        matrix.rows[0].push(XlsCell { cell_value: XlsCellValue::String(PAST_DUE_HEADER.to_string()), formats: vec![XlsCellFormat::Bordered] });
+        for (row_num, m) in materials.iter().enumerate() {
+            let qty: Decimal = init_data.overdue_receipts.iter().filter(|r| r.material == *m).map(|r| r.qty).sum();
+            let value = if qty.is_zero() { XlsCellValue::None } else { XlsCellValue::Decimal(qty) };
+            matrix.rows[row_num + 1].push(XlsCell { cell_value: value, formats: past_due_format(qty) });
+        }
+    }
+
+    // строка дат и колонка материалов закреплены, прошедшие интервалы свернуты в группу
+    matrix.sheet_options.push(XlsSheetOption::FreezePanes { rows: 1, cols: 1 });
+    matrix.sheet_options.push(XlsSheetOption::Autofilter { first_col: 0, last_col: 0 });
+    let past = dates.iter().take_while(|d| **d < now).count();
+    if past > 0 {
+        matrix.sheet_options.push(XlsSheetOption::CollapsedColumns { first_col: 1, last_col: past });
+    }
+    matrix.sheet_options.push(XlsSheetOption::Landscape);
+    matrix.sheet_options.push(XlsSheetOption::FitToWidth);
+    matrix.sheet_options.push(XlsSheetOption::RepeatRows { first_row: 0, last_row: 0 });
+    Ok(matrix)
+}
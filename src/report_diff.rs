@@ -0,0 +1,144 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_xlsxwriter::{Color, Worksheet};
+use crate::projection::Projection;
+use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsCellValue, XlsMatrix};
+
+const MOVED_COLOR: u32 = 0xFFEB9C;
+const NEW_COLOR: u32 = 0xFFC7CE;
+const RESOLVED_COLOR: u32 = 0xC6EFCE;
+pub(crate) const CHANGED_COLOR: u32 = 0xDDEBF7;
+const DATE_FORMAT: &str = "dd.mm.yyyy";
+
+/// Изменение даты первого дефицита материала
+pub struct ShortageChange {
+    pub material: String,
+    pub old: Option<NaiveDate>,
+    pub new: Option<NaiveDate>
+}
+
+pub struct CellChange {
+    pub material: String,
+    pub date: NaiveDate,
+    pub old: Decimal,
+    pub new: Decimal
+}
+
+/// Сравнение текущего прогноза с прогнозом предыдущего (или выбранного) запуска
+pub struct ReportDiff {
+    pub previous_name: String,
+    pub moved_shortages: Vec<ShortageChange>,
+    pub new_shortages: Vec<ShortageChange>,
+    pub resolved_shortages: Vec<ShortageChange>,
+    pub changed_cells: Vec<CellChange>
+}
+
+impl ReportDiff {
+
+    pub fn compare(previous_name: &str, previous: &Projection, current: &Projection) -> ReportDiff {
+        let mut diff = ReportDiff {
+            previous_name: previous_name.to_string(),
+            moved_shortages: vec![],
+            new_shortages: vec![],
+            resolved_shortages: vec![],
+            changed_cells: vec![],
+        };
+
+        let mut materials: Vec<&String> = current.materials.iter().collect();
+        for m in previous.materials.iter() {
+            if !materials.contains(&m) {
+                materials.push(m);
+            }
+        }
+        materials.sort();
+
+        for material in materials {
+            let change = ShortageChange {
+                material: material.clone(),
+                old: previous.first_shortage(material),
+                new: current.first_shortage(material),
+            };
+            match (change.old, change.new) {
+                (None, Some(_)) => diff.new_shortages.push(change),
+                (Some(_), None) => diff.resolved_shortages.push(change),
+                (Some(old), Some(new)) if old != new => diff.moved_shortages.push(change),
+                _ => ()
+            }
+        }
+
+        for (row, material) in current.materials.iter().enumerate() {
+            for (col, date) in current.dates.iter().enumerate() {
+                let new = current.balances[row][col];
+                if let Some(old) = previous.get(material, date) {
+                    if old != new {
+                        diff.changed_cells.push(CellChange { material: material.clone(), date: *date, old, new });
+                    }
+                }
+            }
+        }
+        diff
+    }
+
+    /// Цвет материала, у которого изменилась дата первого дефицита
+    pub fn material_color(&self, material: &str) -> Option<u32> {
+        let marks = [
+            (&self.moved_shortages, MOVED_COLOR),
+            (&self.new_shortages, NEW_COLOR),
+            (&self.resolved_shortages, RESOLVED_COLOR)
+        ];
+        marks.iter()
+            .find(|(changes, _)| changes.iter().any(|c| c.material == material))
+            .map(|(_, color)| *color)
+    }
+
+    /// Выделение изменений в матрице отчета, построенной по projection
+    pub fn mark_matrix(&self, matrix: &mut XlsMatrix, projection: &Projection) {
+        for (row, material) in projection.materials.iter().enumerate() {
+            if let Some(color) = self.material_color(material) {
+                matrix.rows[row + 1][0].formats.push(XlsCellFormat::Background(Color::RGB(color)));
+            }
+        }
+        for change in self.changed_cells.iter() {
+            let row = projection.materials.iter().position(|m| *m == change.material).unwrap();
+            let col = projection.dates.iter().position(|d| *d == change.date).unwrap();
+            matrix.rows[row + 1][col + 1].formats.push(XlsCellFormat::Background(Color::RGB(CHANGED_COLOR)));
+        }
+    }
+
+    pub fn write_to_worksheet(&self, sheet: &mut Worksheet) {
+        let mut matrix = XlsMatrix::new();
+        matrix.rows.push(vec![
+            XlsCell { cell_value: XlsCellValue::String(format!("Сравнение с расчетом: {}", self.previous_name)), formats: vec![] }
+        ]);
+        matrix.rows.push(["Изменение", "Материал", "Дата", "Было", "Стало"].iter()
+            .map(|h| XlsCell::text(h).with_format(XlsCellFormat::Background(Color::RGB(0xEEEEEE))))
+            .collect());
+
+        let sections = [
+            ("Дефицит сдвинулся", &self.moved_shortages, MOVED_COLOR),
+            ("Новый дефицит", &self.new_shortages, NEW_COLOR),
+            ("Дефицит закрыт", &self.resolved_shortages, RESOLVED_COLOR)
+        ];
+        for (title, changes, color) in sections {
+            for change in changes.iter() {
+                matrix.rows.push(vec![
+                    XlsCell::text(title).with_format(XlsCellFormat::Background(Color::RGB(color))),
+                    XlsCell::text(&change.material),
+                    XlsCell::empty(),
+                    change.old.map_or_else(XlsCell::empty, |d| XlsCell::date(d, DATE_FORMAT)),
+                    change.new.map_or_else(XlsCell::empty, |d| XlsCell::date(d, DATE_FORMAT)),
+                ]);
+            }
+        }
+        for change in self.changed_cells.iter() {
+            matrix.rows.push(vec![
+                XlsCell::text("Изменился остаток").with_format(XlsCellFormat::Background(Color::RGB(CHANGED_COLOR))),
+                XlsCell::text(&change.material),
+                XlsCell::date(change.date, DATE_FORMAT),
+                XlsCell::decimal(change.old),
+                XlsCell::decimal(change.new),
+            ]);
+        }
+        matrix.write_to_worksheet(sheet);
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use rust_xlsxwriter::{Color, Workbook};
+use crate::init_data::InitialData;
+use crate::orders_plan::{calculate_need_for_materials, calculate_stocks_plan};
+use crate::projection::Projection;
+use crate::report::{collect_shortages, ShortageSummary};
+use crate::settings::Settings;
+use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsMatrix, XlsSheetOption};
+use crate::xlsx::{get_template_path, read_purchase_plan_file, read_scenarios};
+
+const BASE_NAME: &str = "Базовый";
+/// Фон дефицита, который в сценарии наступает в другую дату, чем в базовом расчете
+const DIFFERENT_COLOR: u32 = 0xFFEB9C;
+
+/// Сценарий "что если": изменения исходных данных относительно базового расчета
+pub struct Scenario {
+    pub(crate) name: String,
+    /// Дополнительные файлы плана обеспечения
+    pub(crate) plan_files: Vec<String>,
+    /// Множитель количества по товару
+    pub(crate) multipliers: Vec<(String, Decimal)>,
+    /// Сдвиг поступлений по номеру заказа, недели
+    pub(crate) order_delays: Vec<(String, u64)>,
+    /// Сдвиг поступлений материалов поставщика, недели
+    pub(crate) supplier_delays: Vec<(String, u64)>,
+    /// Срок доставки материала в единицах из файла сроков доставки
+    pub(crate) lead_times: Vec<(String, u32)>
+}
+
+impl Scenario {
+
+    pub fn new(name: &str) -> Self {
+        Scenario {
+            name: name.to_string(),
+            plan_files: vec![],
+            multipliers: vec![],
+            order_delays: vec![],
+            supplier_delays: vec![],
+            lead_times: vec![],
+        }
+    }
+
+    pub fn add_parameter(&mut self, parameter: &str, object: String, value: Option<Decimal>) -> Result<(), String> {
+        let parameter = parameter.trim().to_lowercase();
+        if object.is_empty() {
+            return Err(format!("Не указан объект параметра '{}'", parameter));
+        }
+        if parameter == "план" {
+            self.plan_files.push(object);
+            return Ok(());
+        }
+        let value = value.ok_or(format!("Не указано значение параметра '{}'", parameter))?;
+        let whole = || value.to_u32().filter(|v| Decimal::from(*v) == value)
+            .ok_or(format!("Значение параметра '{}' должно быть целым неотрицательным числом", parameter));
+        match parameter.as_str() {
+            "множитель" => self.multipliers.push((object, value)),
+            "задержка заказа" => self.order_delays.push((object, whole()? as u64)),
+            "задержка поставщика" => self.supplier_delays.push((object, whole()? as u64)),
+            "срок доставки" => self.lead_times.push((object, whole()?)),
+            _ => return Err(format!("Неизвестный параметр сценария '{}'", parameter))
+        }
+        Ok(())
+    }
+
+    /// Применение сценария к исходным данным базового расчета
+    pub fn apply(&self, init_data: &mut InitialData, settings: &Settings) {
+        for file in self.plan_files.iter() {
+            init_data.purchase_plan_items.extend(read_purchase_plan_file(get_template_path(file), None, settings));
+        }
+        for ppi in init_data.purchase_plan_items.iter_mut() {
+            for (product, multiplier) in self.multipliers.iter() {
+                if ppi.product_name == *product {
+                    ppi.qty *= multiplier;
+                }
+            }
+        }
+        let delay = |date: NaiveDate, weeks: u64| settings.period.start(&(date + Days::new(weeks * 7)));
+        for po in init_data.purchase_orders.iter_mut() {
+            for (order, weeks) in self.order_delays.iter() {
+                if po.name == *order {
+                    po.items.iter_mut().for_each(|mi| mi.date = delay(mi.date, *weeks));
+                }
+            }
+            for mi in po.items.iter_mut() {
+                let supplier = init_data.delivery_times.iter()
+                    .find(|dt| dt.material_name == mi.material)
+                    .and_then(|dt| dt.supplier.as_ref());
+                for (name, weeks) in self.supplier_delays.iter() {
+                    if supplier == Some(name) {
+                        mi.date = delay(mi.date, *weeks);
+                    }
+                }
+            }
+        }
+        for dt in init_data.delivery_times.iter_mut() {
+            for (material, qty) in self.lead_times.iter() {
+                if dt.material_name == *material {
+                    dt.qty = *qty;
+                }
+            }
+        }
+    }
+}
+
+fn calculate_shortages(init_data: &InitialData, settings: &Settings) -> Result<Vec<ShortageSummary>, String> {
+    let need_for_materials = calculate_need_for_materials(init_data)?;
+    let projection = Projection::from_stocks_plan(&calculate_stocks_plan(&need_for_materials, init_data));
+    collect_shortages(&projection, init_data, settings)
+}
+
+/// Сравнение дефицитов: по материалу первый дефицит, минимальный остаток и признак горизонта в каждом сценарии
+fn comparison_matrix(results: &[(String, Vec<ShortageSummary>)], settings: &Settings) -> XlsMatrix {
+    let materials: BTreeSet<&String> = results.iter().flat_map(|(_, shortages)| shortages.iter().map(|s| &s.material)).collect();
+
+    let mut matrix = XlsMatrix::new();
+    let mut names_row = vec![XlsCell::text("")];
+    let mut headers_row = vec![XlsCell::text("Материал")];
+    for (name, _) in results.iter() {
+        names_row.extend([XlsCell::text(name), XlsCell::text(""), XlsCell::text("")]);
+        headers_row.extend([XlsCell::text("Первый дефицит"), XlsCell::text("Мин. остаток"), XlsCell::text("В горизонте срока")]);
+    }
+    matrix.rows.push(names_row);
+    matrix.rows.push(headers_row);
+
+    for material in materials {
+        let base = results[0].1.iter().find(|s| s.material == *material).map(|s| s.first_shortage);
+        let mut row = vec![XlsCell::text(material)];
+        for (_, shortages) in results.iter() {
+            let Some(shortage) = shortages.iter().find(|s| s.material == *material) else {
+                row.extend([XlsCell::text("-"), XlsCell::text(""), XlsCell::text("")]);
+                continue;
+            };
+            let mut first_shortage = XlsCell::date(shortage.first_shortage, settings.period.num_format());
+            if base != Some(shortage.first_shortage) {
+                first_shortage = first_shortage.with_format(XlsCellFormat::Background(Color::RGB(DIFFERENT_COLOR)));
+            }
+            row.push(first_shortage);
+            row.push(XlsCell::decimal(shortage.min_balance));
+            row.push(XlsCell::text(if shortage.inside_lead_time_horizon { "да" } else { "нет" }));
+        }
+        matrix.rows.push(row);
+    }
+    matrix.sheet_options.push(XlsSheetOption::FreezePanes { rows: 2, cols: 1 });
+    matrix
+}
+
+/// Расчет базового варианта и каждого сценария из каталога "Сценарии", сравнение дефицитов в файле path
+pub fn compare_scenarios(path: &str, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let scenarios = read_scenarios();
+    if scenarios.is_empty() {
+        return Err("Нет файлов сценариев в каталоге \"Сценарии\"".into());
+    }
+    let mut results = vec![(BASE_NAME.to_string(), calculate_shortages(&crate::read_initial_data(settings), settings)?)];
+    for scenario in scenarios.iter() {
+        let mut init_data = crate::read_initial_data(settings);
+        scenario.apply(&mut init_data, settings);
+        results.push((scenario.name.clone(), calculate_shortages(&init_data, settings)?));
+    }
+    for (name, shortages) in results.iter() {
+        println!("{}: материалов с дефицитом - {}", name, shortages.len());
+    }
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet().set_name("Сравнение сценариев")?;
+    comparison_matrix(&results, settings).write_to_worksheet(sheet);
+    sheet.autofit();
+    workbook.save(path)?;
+    Ok(())
+}
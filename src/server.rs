@@ -0,0 +1,232 @@
+use std::error::Error;
+use std::panic;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+use crate::html_report::{build_html, escape, STYLE};
+use crate::init_data::InitialData;
+use crate::json_export::build_json_projection;
+use crate::ledger::build_ledger;
+use crate::orders_plan::{calculate_need_for_materials, calculate_stocks_plan, MaterialInfo};
+use crate::projection::Projection;
+use crate::report::{build_matrix, collect_shortages};
+use crate::settings::Settings;
+use crate::xls_matrix::XlsMatrix;
+
+/// Результат расчета, который отдает веб-интерфейс до следующего перечитывания исходных данных
+struct State {
+    calculated_at: NaiveDateTime,
+    init_data: InitialData,
+    need_for_materials: Vec<MaterialInfo>,
+    projection: Projection,
+    matrix: XlsMatrix
+}
+
+/// Движение материала в интервале: поступление по заказу или потребность по строке плана
+#[derive(Serialize)]
+struct Movement {
+    movement: &'static str,
+    document: String,
+    qty: Decimal
+}
+
+#[derive(Serialize)]
+struct MaterialPeriod {
+    date: NaiveDate,
+    movements: Vec<Movement>,
+    balance: Decimal
+}
+
+/// Расшифровка прогноза по материалу: движения по интервалам с накопленным остатком
+#[derive(Serialize)]
+struct MaterialDetail {
+    material: String,
+    first_shortage: Option<NaiveDate>,
+    periods: Vec<MaterialPeriod>
+}
+
+/// Расчет в памяти, без архива и истории. Исходные данные читаются с паникой на ошибках,
+/// поэтому она перехватывается: ошибка в файле не должна останавливать сервер
+fn calculate(settings: &Settings) -> Result<State, String> {
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| -> Result<State, String> {
+        let init_data = crate::read_initial_data(settings);
+        let need_for_materials = calculate_need_for_materials(&init_data)?;
+        let projection = Projection::from_stocks_plan(&calculate_stocks_plan(&need_for_materials, &init_data));
+        let matrix = build_matrix(&projection, &init_data, settings)?;
+        Ok(State { calculated_at: Local::now().naive_local(), init_data, need_for_materials, projection, matrix })
+    }));
+    result.unwrap_or_else(|_| Err("ошибка чтения исходных данных".to_string()))
+}
+
+fn material_detail(state: &State, material: &str) -> Result<Option<MaterialDetail>, String> {
+    if !state.projection.materials.iter().any(|m| m == material) {
+        return Ok(None);
+    }
+    let mut periods: Vec<MaterialPeriod> = vec![];
+    for entry in build_ledger(&state.init_data)?.into_iter().filter(|e| e.material == material) {
+        if periods.last().map(|p| p.date) != Some(entry.date) {
+            periods.push(MaterialPeriod { date: entry.date, movements: vec![], balance: entry.balance });
+        }
+        let period = periods.last_mut().unwrap();
+        period.movements.push(Movement { movement: entry.movement.name(), document: entry.document, qty: entry.qty });
+        period.balance = entry.balance;
+    }
+    Ok(Some(MaterialDetail {
+        material: material.to_string(),
+        first_shortage: state.projection.first_shortage(material),
+        periods
+    }))
+}
+
+fn toolbar(state: &State, message: &str) -> String {
+    format!("<form method=\"post\" action=\"/reload\" style=\"margin-bottom: 8px\">\
+        <button type=\"submit\">Перечитать исходные данные</button> \
+        Расчет от {} {}</form>\
+        <script>document.addEventListener('click', function (e) {{\
+          var tr = e.target.closest('tr[data-material]');\
+          if (tr && e.target.tagName !== 'TH') location.href = '/material?name=' + encodeURIComponent(tr.dataset.material);\
+        }});</script>",
+        state.calculated_at.format("%d.%m.%Y %H:%M:%S"), escape(message))
+}
+
+fn material_html(detail: &MaterialDetail) -> String {
+    let mut html = String::from("<!DOCTYPE html><html lang=\"ru\"><head><meta charset=\"utf-8\">");
+    html.push_str(&format!("<title>{}</title><style>{}</style></head><body>", escape(&detail.material), STYLE));
+    html.push_str("<a href=\"/\">&larr; Состояние заказов</a>");
+    html.push_str(&format!("<h2>{}</h2>", escape(&detail.material)));
+    if let Some(date) = detail.first_shortage {
+        html.push_str(&format!("<p>Первый дефицит: {}</p>", date.format("%d.%m.%Y")));
+    }
+    html.push_str("<table class=\"summary\"><thead><tr><th>Дата</th><th>Движение</th><th>Документ</th>\
+        <th>Количество</th><th>Остаток</th></tr></thead><tbody>");
+    for period in detail.periods.iter() {
+        for (i, movement) in period.movements.iter().enumerate() {
+            let date = if i == 0 { period.date.format("%d.%m.%Y").to_string() } else { String::new() };
+            let balance = if i + 1 == period.movements.len() { period.balance.normalize().to_string() } else { String::new() };
+            let color = if balance.starts_with('-') { " style=\"color: #FF0000\"" } else { "" };
+            html.push_str(&format!("<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\"{}>{}</td></tr>",
+                date, movement.movement, escape(&movement.document), movement.qty.normalize(), color, balance));
+        }
+    }
+    html.push_str("</tbody></table></body></html>");
+    html
+}
+
+/// Декодирование параметра запроса: %XX и "+" вместо пробела
+fn url_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => result.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(b) => {
+                        result.push(b);
+                        i += 2;
+                    }
+                    None => result.push(b'%')
+                }
+            }
+            b => result.push(b)
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&result).to_string()
+}
+
+fn query_param(url: &str, name: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| url_decode(value))
+}
+
+fn respond(request: Request, status: u16, content_type: &str, body: String) {
+    let header = Header::from_bytes("Content-Type", format!("{}; charset=utf-8", content_type)).unwrap();
+    if let Err(e) = request.respond(Response::from_string(body).with_status_code(status).with_header(header)) {
+        println!("Ошибка отправки ответа: {}", e);
+    }
+}
+
+fn respond_json<T: Serialize>(request: Request, value: &Result<T, String>) {
+    match value {
+        Ok(value) => respond(request, 200, "application/json", serde_json::to_string_pretty(value).unwrap()),
+        Err(e) => respond(request, 500, "text/plain", e.clone())
+    }
+}
+
+fn redirect(request: Request, location: &str) {
+    let header = Header::from_bytes("Location", location).unwrap();
+    let _ = request.respond(Response::empty(303).with_header(header));
+}
+
+/// Локальный веб-интерфейс: http://127.0.0.1:<порт>/
+///
+/// - `/` - состояние заказов и сводка дефицитов, кнопка перечитывания исходных данных
+/// - `/material?name=` - движения материала по интервалам: потребности по строкам плана и поступления по заказам
+/// - `/api/projection` - прогноз в формате выгрузки JSON
+/// - `/api/shortages` - сводка дефицитов
+/// - `/api/material?name=` - движения материала
+/// - `POST /reload` - перечитать исходные данные
+pub fn serve(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let mut state = calculate(settings)?;
+    let mut message = String::new();
+    let address = format!("127.0.0.1:{}", settings.port);
+    let server = Server::http(&address).map_err(|e| format!("Не удалось запустить веб-интерфейс на {}: {}", address, e))?;
+    println!("Веб-интерфейс запущен: http://{}/, для выхода нажмите Ctrl+C", address);
+
+    for request in server.incoming_requests() {
+        let url = request.url().to_string();
+        let path = url.split('?').next().unwrap_or_default();
+        match (request.method(), path) {
+            (Method::Get, "/") => {
+                let html = build_html(&state.matrix, &state.projection, &state.init_data, settings, &toolbar(&state, &message));
+                match html {
+                    Ok(html) => respond(request, 200, "text/html", html),
+                    Err(e) => respond(request, 500, "text/plain", e)
+                }
+            }
+            (Method::Get, "/material") => {
+                let material = query_param(&url, "name").unwrap_or_default();
+                match material_detail(&state, &material) {
+                    Ok(Some(detail)) => respond(request, 200, "text/html", material_html(&detail)),
+                    Ok(None) => respond(request, 404, "text/plain", format!("Материал '{}' не найден", material)),
+                    Err(e) => respond(request, 500, "text/plain", e)
+                }
+            }
+            (Method::Get, "/api/projection") => {
+                respond_json(request, &build_json_projection(&state.projection, &state.need_for_materials, &state.init_data, settings));
+            }
+            (Method::Get, "/api/shortages") => {
+                respond_json(request, &collect_shortages(&state.projection, &state.init_data, settings));
+            }
+            (Method::Get, "/api/material") => {
+                let material = query_param(&url, "name").unwrap_or_default();
+                match material_detail(&state, &material) {
+                    Ok(None) => respond(request, 404, "text/plain", format!("Материал '{}' не найден", material)),
+                    detail => respond_json(request, &detail.map(|d| d.unwrap()))
+                }
+            }
+            (Method::Post, "/reload") => {
+                match calculate(settings) {
+                    Ok(new_state) => {
+                        state = new_state;
+                        message = String::new();
+                        println!("Исходные данные перечитаны");
+                    }
+                    Err(e) => {
+                        message = format!("(не удалось перечитать исходные данные: {}, показан предыдущий расчет)", e);
+                        println!("Ошибка расчета: {}", e);
+                    }
+                }
+                redirect(request, "/");
+            }
+            _ => respond(request, 404, "text/plain", "Страница не найдена".to_string())
+        }
+    }
+    Ok(())
+}
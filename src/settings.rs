@@ -0,0 +1,154 @@
+use crate::period::Period;
+
+/// Формат файла результата
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Xlsx,
+    Json,
+    Html
+}
+
+impl OutputFormat {
+
+    pub fn parse(value: &str) -> Result<OutputFormat, String> {
+        match value.trim().to_lowercase().as_str() {
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            "json" => Ok(OutputFormat::Json),
+            "html" => Ok(OutputFormat::Html),
+            _ => Err(format!("Неизвестный формат результата '{}' (допустимо: xlsx, json, html)", value))
+        }
+    }
+}
+
+/// Учет поступлений по заказам с датой раньше текущего интервала, не подтвержденных как полученные
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverdueMode {
+    /// Учитывать на дату из заказа
+    Keep,
+    /// Перенести в текущий интервал
+    CurrentPeriod,
+    /// Не учитывать в остатке, показать отдельной колонкой "Просрочено" в отчете
+    PastDue
+}
+
+impl OverdueMode {
+
+    pub fn parse(value: &str) -> Result<OverdueMode, String> {
+        match value.trim().to_lowercase().as_str() {
+            "keep" | "как есть" => Ok(OverdueMode::Keep),
+            "current" | "текущий" => Ok(OverdueMode::CurrentPeriod),
+            "past_due" | "просрочено" => Ok(OverdueMode::PastDue),
+            _ => Err(format!("Неизвестный режим просроченных поступлений '{}' (допустимо: current, past_due, keep)", value))
+        }
+    }
+}
+
+pub const DEFAULT_HISTORY_DB: &str = "История расчетов.sqlite";
+
+/// Параметры запуска, задаются аргументами вида `-имя=значение`
+pub struct Settings {
+    pub period: Period,
+    /// Форматы результата, через запятую: -format=xlsx,json,html
+    pub formats: Vec<OutputFormat>,
+    /// Архивный расчет для сравнения, по умолчанию - последний в каталоге "Архив"
+    pub compare: Option<String>,
+    /// База истории расчетов (SQLite), если задана - каждый запуск сохраняется в нее
+    pub history_db: Option<String>,
+    /// Журнал движений: файл "Движения.csv" и лист "Движения" в отчете xlsx
+    pub ledger: bool,
+    /// Лист, из которого читаются файлы исходных данных, по умолчанию - первый лист
+    pub sheet: Option<String>,
+    /// Сводная книга со всеми исходными данными на листах "План", "Остатки", "Сроки", "Спецификации" и "Заказы"
+    pub workbook: Option<String>,
+    /// Порт веб-интерфейса (команда -serve)
+    pub port: u16,
+    /// Лист "Состояние заказов" с формулами: строки остатков, поступлений и потребности по материалу,
+    /// накопленный остаток пересчитывается в Excel
+    pub formulas: bool,
+    /// Лист "Выполнимость плана" в отчете xlsx: сколько товара по каждой строке плана можно собрать
+    pub feasibility: bool,
+    /// Лист "Распределение" в отчете xlsx: распределение материалов по строкам плана
+    /// в порядке приоритетов из файла "Приоритеты"
+    pub allocation: bool,
+    /// Листы "Исх. ..." в отчете xlsx: исходные данные расчета, файлы и их хеши
+    pub audit: bool,
+    /// Лист "Графики" в отчете xlsx: -charts - по всем материалам с дефицитом,
+    /// -charts=<материал>;<материал> - по выбранным
+    pub charts: Option<Vec<String>>,
+    /// Просроченные поступления по заказам: -overdue=current (по умолчанию) - в текущий интервал,
+    /// -overdue=past_due - не учитывать в остатке, отдельная колонка в отчете, -overdue=keep - на дату из заказа
+    pub overdue: OverdueMode
+}
+
+impl Settings {
+
+    pub fn from_args(args: &[String]) -> Result<Settings, String> {
+        let mut settings = Settings {
+            period: Period::Week,
+            formats: vec![OutputFormat::Xlsx],
+            compare: None,
+            history_db: None,
+            ledger: false,
+            sheet: None,
+            workbook: None,
+            port: 8080,
+            formulas: false,
+            feasibility: false,
+            allocation: false,
+            audit: false,
+            charts: None,
+            overdue: OverdueMode::CurrentPeriod
+        };
+        for argument in args {
+            if let Some(value) = argument.strip_prefix("-period=") {
+                settings.period = Period::parse(value)?;
+            }
+            if let Some(value) = argument.strip_prefix("-format=") {
+                settings.formats = value.split(',').map(OutputFormat::parse).collect::<Result<_, _>>()?;
+            }
+            if let Some(value) = argument.strip_prefix("-compare=") {
+                settings.compare = Some(value.to_string());
+            }
+            if let Some(value) = argument.strip_prefix("-sheet=") {
+                settings.sheet = Some(value.to_string());
+            }
+            if let Some(value) = argument.strip_prefix("-workbook=") {
+                settings.workbook = Some(value.to_string());
+            }
+            if let Some(value) = argument.strip_prefix("-port=") {
+                settings.port = value.parse().map_err(|_| format!("Неверный номер порта '{}'", value))?;
+            }
+            if let Some(value) = argument.strip_prefix("-overdue=") {
+                settings.overdue = OverdueMode::parse(value)?;
+            }
+            if argument == "-feasibility" {
+                settings.feasibility = true;
+            }
+            if argument == "-allocation" {
+                settings.allocation = true;
+            }
+            if argument == "-audit" {
+                settings.audit = true;
+            }
+            if argument == "-formulas" {
+                settings.formulas = true;
+            }
+            if argument == "-charts" {
+                settings.charts = Some(vec![]);
+            }
+            if let Some(value) = argument.strip_prefix("-charts=") {
+                settings.charts = Some(value.split(';').map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).collect());
+            }
+            if argument == "-ledger" {
+                settings.ledger = true;
+            }
+            if argument == "-history_db" {
+                settings.history_db = Some(DEFAULT_HISTORY_DB.to_string());
+            }
+            if let Some(value) = argument.strip_prefix("-history_db=") {
+                settings.history_db = Some(value.to_string());
+            }
+        }
+        Ok(settings)
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_xlsxwriter::{Color, Worksheet};
+use crate::init_data::InitialData;
+use crate::orders_plan::MaterialInfo;
+use crate::xls_matrix::{XlsCell, XlsCellFormat, XlsMatrix};
+
+/// Инвентаризация материала: остаток из файла остатков - посчитанное количество на начало интервала date
+pub struct StockCount<'a> {
+    pub material: &'a String,
+    pub date: NaiveDate,
+    pub counted: Decimal,
+    /// Остаток, который ожидался по прогнозу на эту дату; None, если до инвентаризации движений не было
+    pub expected: Option<Decimal>
+}
+
+impl StockCount<'_> {
+
+    /// Движение, которое приводит прогнозный остаток к посчитанному
+    pub fn adjustment(&self) -> Decimal {
+        self.counted - self.expected.unwrap_or_default()
+    }
+}
+
+/// Инвентаризации по материалам в порядке дат. Ожидаемый остаток - предыдущая инвентаризация
+/// плюс поступления и минус потребность в интервалах между ними: прогноз начинается заново от каждой инвентаризации.
+/// Если на одну дату несколько значений, используется последнее.
+pub fn calculate_stock_counts<'a>(requirements: &[MaterialInfo], data_set: &'a InitialData) -> Vec<StockCount<'a>> {
+    let mut counts: Vec<StockCount> = vec![];
+    for mi in data_set.stocks.iter() {
+        match counts.iter_mut().find(|c| *c.material == mi.material && c.date == mi.date) {
+            Some(count) => count.counted = mi.qty,
+            None => counts.push(StockCount { material: &mi.material, date: mi.date, counted: mi.qty, expected: None })
+        }
+    }
+    counts.sort_by(|a, b| (a.material, a.date).cmp(&(b.material, b.date)));
+
+    let mut movements: HashMap<&String, Vec<&MaterialInfo>> = HashMap::new();
+    for mi in requirements.iter().chain(data_set.purchase_orders.iter().flat_map(|po| po.items.iter())) {
+        movements.entry(&mi.material).or_default().push(mi);
+    }
+
+    for i in 0..counts.len() {
+        let previous = if i > 0 && counts[i - 1].material == counts[i].material { Some((counts[i - 1].date, counts[i - 1].counted)) } else { None };
+        let between: Vec<Decimal> = movements.get(counts[i].material).into_iter().flatten()
+            .filter(|mi| mi.date < counts[i].date && previous.is_none_or(|(date, _)| mi.date >= date))
+            .map(|mi| mi.qty)
+            .collect();
+        if previous.is_some() || !between.is_empty() {
+            counts[i].expected = Some(previous.map(|(_, counted)| counted).unwrap_or_default() + between.iter().sum::<Decimal>());
+        }
+    }
+    counts
+}
+
+/// Инвентаризации как движения для накопленного остатка
+pub fn stock_movements(counts: &[StockCount]) -> Vec<MaterialInfo> {
+    counts.iter()
+        .map(|c| MaterialInfo { date: c.date, material: c.material.clone(), qty: c.adjustment() })
+        .collect()
+}
+
+/// Лист "Сверка остатков": посчитанный и ожидаемый по прогнозу остаток для инвентаризаций, перед которыми были движения
+pub fn write_to_worksheet(sheet: &mut Worksheet, counts: &[StockCount], num_format: &'static str) {
+    let mut rows = vec![];
+    for count in counts.iter() {
+        let Some(expected) = count.expected else {
+            continue;
+        };
+        let mut row = vec![
+            XlsCell::text(count.material),
+            XlsCell::date(count.date, num_format),
+            XlsCell::decimal(count.counted),
+            XlsCell::decimal(expected),
+            XlsCell::decimal(count.counted - expected),
+        ];
+        if count.counted != expected {
+            row = row.into_iter().map(|c| c.with_format(XlsCellFormat::Background(Color::RGB(0xFFEB9C)))).collect();
+        }
+        rows.push(row);
+    }
+    let matrix = XlsMatrix::table(&["Материал", "Дата", "Посчитано", "Ожидалось", "Расхождение"], rows, 1);
+    matrix.write_to_worksheet(sheet);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use crate::init_data::{InitialData, OrderStatus, PurchaseOrder};
+    use crate::orders_plan::MaterialInfo;
+    use super::{calculate_stock_counts, stock_movements};
+
+    fn mi(material: &str, date: (i32, u32, u32), qty: i64) -> MaterialInfo {
+        MaterialInfo { date: NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(), material: material.to_string(), qty: Decimal::from(qty) }
+    }
+
+    fn order(items: Vec<MaterialInfo>) -> PurchaseOrder {
+        PurchaseOrder { name: "1".to_string(), items, status: OrderStatus::Open, order_date: None }
+    }
+
+    #[test]
+    fn single_snapshot_is_counted_quantity() {
+        let mut data = InitialData::empty();
+        data.stocks = vec![mi("A", (2024, 4, 1), 100)];
+        let requirements = vec![mi("A", (2024, 4, 8), -30)];
+
+        let counts = calculate_stock_counts(&requirements, &data);
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[0].expected, None);
+        assert_eq!(counts[0].adjustment(), Decimal::from(100));
+    }
+
+    #[test]
+    fn receipt_between_counts_is_expected() {
+        let mut data = InitialData::empty();
+        data.stocks = vec![mi("A", (2024, 4, 1), 100), mi("A", (2024, 4, 15), 90)];
+        data.purchase_orders = vec![order(vec![mi("A", (2024, 4, 8), 50)])];
+        let requirements = vec![mi("A", (2024, 4, 8), -40), mi("A", (2024, 4, 22), -10)];
+
+        let counts = calculate_stock_counts(&requirements, &data);
+        assert_eq!(counts.len(), 2);
+        // 100 + 50 - 40 = 110 ожидалось, посчитано 90
+        assert_eq!(counts[1].expected, Some(Decimal::from(110)));
+        assert_eq!(counts[1].adjustment(), Decimal::from(-20));
+
+        // накопленный остаток по движениям равен последней инвентаризации
+        let balance: Decimal = stock_movements(&counts).iter().map(|m| m.qty)
+            .chain([Decimal::from(50), Decimal::from(-40)])
+            .sum();
+        assert_eq!(balance, Decimal::from(90));
+    }
+
+    #[test]
+    fn material_without_count_has_no_adjustment() {
+        let mut data = InitialData::empty();
+        data.stocks = vec![mi("A", (2024, 4, 1), 100)];
+        data.purchase_orders = vec![order(vec![mi("B", (2024, 4, 8), 50)])];
+        let requirements = vec![mi("B", (2024, 4, 15), -20)];
+
+        let counts = calculate_stock_counts(&requirements, &data);
+        assert!(counts.iter().all(|c| c.material == "A"));
+        assert!(stock_movements(&counts).iter().all(|m| m.material != "B"));
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use crate::projection::Projection;
+use crate::report_diff::ReportDiff;
+use crate::settings::Settings;
+use crate::xlsx::{get_template_path, is_input_file};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Excel сохраняет файл в несколько приемов, поэтому расчет запускается,
+/// только когда файлы не менялись в течение этого времени
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+type Snapshot = HashMap<PathBuf, (SystemTime, u64)>;
+
+fn take_snapshot(dir: &Path, snapshot: &mut Snapshot) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            take_snapshot(&path, snapshot);
+        } else if is_input_file(path.to_str().unwrap_or_default()) {
+            snapshot.insert(path, (metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len()));
+        }
+    }
+}
+
+fn changed_files(old: &Snapshot, new: &Snapshot) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = new.iter()
+        .filter(|(path, state)| old.get(*path) != Some(state))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(old.keys().filter(|path| !new.contains_key(*path)).cloned());
+    changed.sort();
+    changed
+}
+
+/// Расчет с перехватом паники: файл, сохраненный не до конца, не должен останавливать наблюдение
+fn run(settings: &Settings) -> Option<Projection> {
+    match panic::catch_unwind(panic::AssertUnwindSafe(|| crate::calculate_orders_state(settings).map_err(|e| e.to_string()))) {
+        Ok(Ok(projection)) => Some(projection),
+        Ok(Err(e)) => {
+            println!("Ошибка расчета: {}", e);
+            None
+        }
+        Err(_) => {
+            println!("Ошибка расчета, ожидание следующего изменения файлов");
+            None
+        }
+    }
+}
+
+fn print_shortage_changes(previous: &Projection, current: &Projection) {
+    let diff = ReportDiff::compare("", previous, current);
+    for change in diff.new_shortages.iter() {
+        println!("  + дефицит: {} с {}", change.material, change.new.unwrap().format("%d.%m.%Y"));
+    }
+    for change in diff.resolved_shortages.iter() {
+        println!("  - дефицит закрыт: {}", change.material);
+    }
+    for change in diff.moved_shortages.iter() {
+        println!("  ~ дефицит сдвинулся: {} {} -> {}", change.material,
+                 change.old.unwrap().format("%d.%m.%Y"), change.new.unwrap().format("%d.%m.%Y"));
+    }
+    if diff.new_shortages.is_empty() && diff.resolved_shortages.is_empty() && diff.moved_shortages.is_empty() {
+        println!("  дефициты не изменились");
+    }
+}
+
+/// Наблюдение за каталогом исходных данных: пересчет отчета после каждого изменения файлов
+pub fn watch(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let dir = PathBuf::from(get_template_path(""));
+    let mut snapshot = Snapshot::new();
+    take_snapshot(&dir, &mut snapshot);
+
+    let mut projection = run(settings);
+    println!("Наблюдение за каталогом \"{}\", для выхода нажмите Ctrl+C", dir.display());
+
+    let mut pending: Vec<PathBuf> = vec![];
+    let mut last_change = Instant::now();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let mut current = Snapshot::new();
+        take_snapshot(&dir, &mut current);
+        let changed = changed_files(&snapshot, &current);
+        if !changed.is_empty() {
+            for path in changed {
+                if !pending.contains(&path) {
+                    pending.push(path);
+                }
+            }
+            snapshot = current;
+            last_change = Instant::now();
+            continue;
+        }
+        if pending.is_empty() || last_change.elapsed() < DEBOUNCE {
+            continue;
+        }
+
+        println!("Изменены файлы:");
+        for path in pending.drain(..) {
+            println!("  {}", path.strip_prefix(&dir).unwrap_or(&path).display());
+        }
+        let new_projection = run(settings);
+        if let (Some(previous), Some(current)) = (&projection, &new_projection) {
+            print_shortage_changes(previous, current);
+        }
+        if new_projection.is_some() {
+            projection = new_projection;
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
-use rust_xlsxwriter::{ColNum, Color, Format, FormatBorder, IntoExcelData, RowNum, Worksheet};
+use rust_xlsxwriter::{ColNum, Color, Format, FormatBorder, IntoExcelData, RowNum, Worksheet, XlsxError};
 
 
 pub enum XlsCellFormat {
@@ -17,13 +17,57 @@ pub enum XlsCellValue {
     Date(NaiveDate),
     String(String)
 }
+/// Настройки листа. Номера строк и колонок - в координатах матрицы
+pub enum XlsSheetOption {
+    /// Закрепить первые rows строк и cols колонок
+    FreezePanes { rows: usize, cols: usize },
+    /// Автофильтр по колонкам first_col..=last_col, заголовок - первая строка матрицы
+    Autofilter { first_col: usize, last_col: usize },
+    /// Свернутая группа колонок
+    CollapsedColumns { first_col: usize, last_col: usize },
+    Landscape,
+    /// Печать в одну страницу по ширине
+    FitToWidth,
+    /// Строки, повторяемые на каждой печатной странице
+    RepeatRows { first_row: usize, last_row: usize },
+    /// Защита листа от изменений
+    Protect
+}
+
 pub struct XlsCell {
     pub cell_value: XlsCellValue,
     pub formats: Vec<XlsCellFormat>
 }
 
+/// Ячейки таблиц в рамке
+impl XlsCell {
+
+    pub fn text(text: &str) -> Self {
+        XlsCell { cell_value: XlsCellValue::String(text.to_string()), formats: vec![XlsCellFormat::Bordered] }
+    }
+
+    pub fn decimal(value: Decimal) -> Self {
+        XlsCell { cell_value: XlsCellValue::Decimal(value), formats: vec![XlsCellFormat::Bordered] }
+    }
+
+    /// Дата в формате num_format (формат интервала расчета или "dd.mm.yyyy")
+    pub fn date(date: NaiveDate, num_format: &'static str) -> Self {
+        XlsCell { cell_value: XlsCellValue::Date(date), formats: vec![XlsCellFormat::Bordered, XlsCellFormat::NumFormat(num_format)] }
+    }
+
+    pub fn empty() -> Self {
+        XlsCell { cell_value: XlsCellValue::None, formats: vec![XlsCellFormat::Bordered] }
+    }
+
+    pub fn with_format(mut self, format: XlsCellFormat) -> Self {
+        self.formats.push(format);
+        self
+    }
+}
+
 pub struct XlsMatrix {
-    pub rows: Vec<Vec<XlsCell>>
+    pub rows: Vec<Vec<XlsCell>>,
+    pub sheet_options: Vec<XlsSheetOption>
 }
 
 pub(crate) fn write_to_cell<T: IntoExcelData>(sheet: &mut Worksheet, row_num: usize, col_num: usize, value: T, formats: &Vec<XlsCellFormat>) {
@@ -43,10 +87,22 @@ impl XlsMatrix {
 
     pub fn new() -> Self {
         XlsMatrix{
-            rows: vec![]
+            rows: vec![],
+            sheet_options: vec![]
         }
     }
 
+    /// Таблица листа: строка заголовков и строки rows. Заголовок и первые freeze_cols колонок закреплены,
+    /// автофильтр по всем колонкам
+    pub fn table(headers: &[&str], rows: Vec<Vec<XlsCell>>, freeze_cols: usize) -> Self {
+        let mut matrix = XlsMatrix::new();
+        matrix.rows.push(headers.iter().map(|h| XlsCell::text(h)).collect());
+        matrix.rows.extend(rows);
+        matrix.sheet_options.push(XlsSheetOption::FreezePanes { rows: 1, cols: freeze_cols });
+        matrix.sheet_options.push(XlsSheetOption::Autofilter { first_col: 0, last_col: headers.len() - 1 });
+        matrix
+    }
+
     pub fn write_to_worksheet(&self, sheet: &mut Worksheet) {
         for row_num in 0..self.rows.len() {
             for col_num in 0..self.rows[row_num].len() {
@@ -60,5 +116,36 @@ impl XlsMatrix {
                 };
             }
         }
+        self.apply_sheet_options(sheet).expect("Ошибка при настройке листа");
+    }
+
+    fn apply_sheet_options(&self, sheet: &mut Worksheet) -> Result<(), XlsxError> {
+        let last_row = self.rows.len().max(1) as RowNum;
+        for option in self.sheet_options.iter() {
+            match option {
+                XlsSheetOption::FreezePanes { rows, cols } => {
+                    sheet.set_freeze_panes((rows + 1) as RowNum, (cols + 1) as ColNum)?;
+                }
+                XlsSheetOption::Autofilter { first_col, last_col } => {
+                    sheet.autofilter(1, (first_col + 1) as ColNum, last_row, (last_col + 1) as ColNum)?;
+                }
+                XlsSheetOption::CollapsedColumns { first_col, last_col } => {
+                    sheet.group_columns_collapsed((first_col + 1) as ColNum, (last_col + 1) as ColNum)?;
+                }
+                XlsSheetOption::Landscape => {
+                    sheet.set_landscape();
+                }
+                XlsSheetOption::FitToWidth => {
+                    sheet.set_print_fit_to_pages(1, 0);
+                }
+                XlsSheetOption::RepeatRows { first_row, last_row } => {
+                    sheet.set_repeat_rows((first_row + 1) as RowNum, (last_row + 1) as RowNum)?;
+                }
+                XlsSheetOption::Protect => {
+                    sheet.protect();
+                }
+            }
+        }
+        Ok(())
     }
 }
\ No newline at end of file
@@ -1,16 +1,114 @@
 use std::{env, fs};
-use calamine::{Data, DataType, open_workbook, Range, Reader, Xlsx};
+use std::path::Path;
+use std::cmp::Reverse;
+use std::time::SystemTime;
+use calamine::{Data, DataType, open_workbook_auto, Range, Reader};
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::FromPrimitive;
-use crate::check_date;
-use crate::init_data::{DeliveryTime, PurchaseOrder, PurchasePlanItem, Specification, SpecificationItem};
+use crate::csv_table::read_csv_range;
+use crate::calendar::{Calendar, LeadTimeUnit};
+use crate::init_data::{DeliveryTime, OrderStatus, PurchaseOrder, PurchasePlanItem, Receipt, Specification, SpecificationItem};
 use crate::orders_plan::MaterialInfo;
+use crate::period::Period;
+use crate::scenario::Scenario;
+use crate::settings::Settings;
 
-fn get_template_path(file_name: &str) -> String {
+pub(crate) fn get_template_path(file_name: &str) -> String {
+    if Path::new(file_name).is_absolute() {
+        return file_name.to_string();
+    }
     format!("{}/{}",env::current_exe().unwrap().parent().unwrap().join("Исходные данные").to_str().unwrap(), file_name)
 }
 
+/// Расширения файлов исходных данных, по расширению выбирается способ чтения
+const INPUT_EXTENSIONS: [&str; 6] = ["xlsx", "xlsm", "xls", "xlsb", "ods", "csv"];
+
+fn get_extension(path: &str) -> String {
+    Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase()
+}
+
+/// Файл исходных данных поддерживаемого формата, кроме временных файлов Excel/LibreOffice ("~$Заказ_100.xlsx")
+pub(crate) fn is_input_file(path: &str) -> bool {
+    let file_name = Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if file_name.starts_with("~$") || file_name.starts_with(".~lock") {
+        return false;
+    }
+    INPUT_EXTENSIONS.contains(&get_extension(path).as_str())
+}
+
+/// Файлы исходных данных name (без расширения) во всех поддерживаемых форматах, самый новый - первым
+fn find_input_paths(name: &str) -> Vec<String> {
+    let mut paths: Vec<(SystemTime, String)> = INPUT_EXTENSIONS.iter()
+        .map(|extension| get_template_path(&format!("{}.{}", name, extension)))
+        .filter_map(|path| fs::metadata(&path).ok().map(|m| (m.modified().unwrap_or(SystemTime::UNIX_EPOCH), path)))
+        .collect();
+    paths.sort_by_key(|(modified, _)| Reverse(*modified));
+    paths.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Путь к файлу исходных данных name (без расширения). Если файл есть в нескольких форматах,
+/// читается самый новый, о чем выводится сообщение. Если файла нет - путь к xlsx
+fn get_input_path(name: &str) -> String {
+    let paths = find_input_paths(name);
+    let Some(path) = paths.first() else {
+        return get_template_path(&format!("{}.xlsx", name));
+    };
+    if paths.len() > 1 {
+        let files: Vec<&str> = paths.iter()
+            .map(|p| Path::new(p).file_name().and_then(|n| n.to_str()).unwrap_or_default())
+            .collect();
+        println!("Найдено несколько файлов \"{}\" ({}), читается самый новый: {}", name, files.join(", "), files[0]);
+    }
+    path.clone()
+}
+
+/// Файлы исходных данных, из которых читается расчет: сводная книга или отдельные файлы и каталоги
+pub fn input_files(settings: &Settings) -> Vec<String> {
+    if let Some(workbook) = &settings.workbook {
+        return vec![get_template_path(workbook)];
+    }
+    let mut result: Vec<String> = ["План обеспечения", "Остатки", "Сроки доставки", "Поступления"].iter()
+        .filter_map(|name| find_input_paths(name).into_iter().next())
+        .collect();
+    for dir in ["Спецификации", "Заказы поставщикам", "Календари"] {
+        let Ok(entries) = fs::read_dir(get_template_path(dir)) else {
+            continue;
+        };
+        let mut files: Vec<String> = entries.flatten()
+            .map(|e| e.path().into_os_string().to_str().unwrap().to_string())
+            .filter(|path| is_input_file(path))
+            .collect();
+        files.sort();
+        result.append(&mut files);
+    }
+    result
+}
+
+/// Лист sheet (или первый лист, если не задан) книги xlsx, xls, xlsb или ods (формат определяет calamine)
+fn read_workbook_range(path: &str, sheet: Option<&str>) -> Range<Data> {
+    let mut workbook = open_workbook_auto(path).unwrap_or_else(|e| panic!("Не удалось открыть файл {}: {}", path, e));
+    if let Some(sheet) = sheet {
+        return workbook.worksheet_range(sheet)
+            .unwrap_or_else(|e| panic!("Не удалось прочитать лист \"{}\" в файле {}: {}", sheet, path, e));
+    }
+    let binding = workbook.worksheets();
+    let worksheet = binding.first().unwrap();
+    worksheet.1.clone()
+}
+
+/// Файл и лист, из которых читаются данные name: отдельный файл "<name>.*"
+/// или лист workbook_sheet сводной книги, если она задана параметром -workbook
+fn get_source(settings: &Settings, name: &str, workbook_sheet: &str) -> (String, Option<String>) {
+    match &settings.workbook {
+        Some(workbook) => (get_template_path(workbook), Some(workbook_sheet.to_string())),
+        None => (get_input_path(name), settings.sheet.clone())
+    }
+}
+
+/// Чтение таблицы из книги xlsx, xls, xlsb, ods или csv, формат определяется по расширению файла.
+/// Лист книги выбирается по имени sheet, если не задан - первый лист.
+///
 /// F - function for read data. args: row, col, &Range
 ///
 /// R - function for determinate range, returns tuple (row, height, col, width) of range.
@@ -22,7 +120,7 @@ fn get_template_path(file_name: &str) -> String {
 /// fn read_data()
 /// {
 ///     let mut data = Data::new();
-///     read_xlsx("file.xlsx".to_string(),
+///     read_table("file.xlsx".to_string(), None,
 ///     |row, col, range| {
 ///         data.push(range.get(row, col));
 ///     },
@@ -32,51 +130,232 @@ fn get_template_path(file_name: &str) -> String {
 /// }
 ///
 /// ```
-pub fn read_xlsx<R, B>(path: String, mut read_func: R, bounds_func: B)
+pub fn read_table<R, B>(path: String, sheet: Option<&str>, mut read_func: R, bounds_func: B)
     where
         R: FnMut(usize, usize,  &Range<Data>),
         B: Fn(&Range<Data>) -> (usize, usize, usize, usize)
 {
-    let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
-    let binding = workbook.worksheets();
-    let worksheet = binding.get(0).unwrap();
-    let range = &(*worksheet).1;
-    let b = bounds_func(range);
+    let range = match get_extension(&path).as_str() {
+        "csv" => read_csv_range(&path),
+        _ => read_workbook_range(&path, sheet)
+    };
+    let b = bounds_func(&range);
     for row in b.0..b.1 {
         for col in b.2..b.3 {
-            read_func(row, col, range);
+            read_func(row, col, &range);
         }
     }
 }
 
-pub fn read_purchase_plan_items() -> Vec<PurchasePlanItem> {
+pub fn read_purchase_plan_items(settings: &Settings) -> Vec<PurchasePlanItem> {
+    let (path, sheet) = get_source(settings, "План обеспечения", "План");
+    read_purchase_plan_file(path, sheet.as_deref(), settings)
+}
+
+/// План обеспечения: строка дат, далее товар и количества по датам.
+/// При недельном планировании даты должны быть понедельниками, иначе - ошибка;
+/// при других интервалах даты относятся к началу интервала с предупреждением
+pub fn read_purchase_plan_file(path: String, sheet: Option<&str>, settings: &Settings) -> Vec<PurchasePlanItem> {
     let mut purchase_plan = vec![];
-    let p = get_template_path("План обеспечения.xlsx");
-    read_xlsx(p, |row, col, range| {
+    let file_name = Path::new(&path).file_name().unwrap().to_string_lossy().to_string();
+    let mut snapped = vec![];
+    read_table(path, sheet, |row, col, range| {
+        let date = range.get((0usize, col)).unwrap().as_date().unwrap();
+        let start = settings.period.check_date(&date, &file_name);
+        if start != date && !snapped.contains(&date) {
+            snapped.push(date);
+        }
         let plan_item = PurchasePlanItem {
             product_name: range.get((row, 0usize)).unwrap().as_string().unwrap(),
-            date: range.get((0usize, col)).unwrap().as_date().unwrap(),
-            qty: Decimal::from_f64(range.get((row, col)).unwrap().as_f64().expect("Не получилось прочитать значение ячейки как число в файле План обеспечения")).unwrap(),
+            date: start,
+            qty: Decimal::from_f64(range.get((row, col)).unwrap().as_f64()
+                .unwrap_or_else(|| panic!("Не получилось прочитать значение ячейки как число в файле {}", file_name))).unwrap(),
         };
         purchase_plan.push(plan_item);
     }, |range| {
         (1, range.height(), 1, range.width())
     });
-    return purchase_plan
+    if !snapped.is_empty() {
+        let dates: Vec<String> = snapped.iter().map(|d| d.format("%d.%m.%Y").to_string()).collect();
+        println!("Внимание: даты плана в файле {} не совпадают с началом интервала ({}) и отнесены к нему: {}",
+                 file_name, settings.period.name(), dates.join(", "));
+    }
+    purchase_plan
 }
 
-pub fn read_delivery_time_items() -> Vec<DeliveryTime> {
+/// Колонки: материал, срок, единица срока (нед, дн, раб. дн; по умолчанию недели), поставщик, страховой запас
+pub fn read_delivery_time_items(settings: &Settings) -> Vec<DeliveryTime> {
     let mut delivery_times = vec![];
-    read_xlsx(get_template_path("Сроки доставки.xlsx"), |row, _, range| {
+    let (path, sheet) = get_source(settings, "Сроки доставки", "Сроки");
+    read_table(path, sheet.as_deref(), |row, _, range| {
+        let unit = range.get((row, 2usize)).and_then(|d| d.as_string()).unwrap_or_default();
+        let supplier = range.get((row, 3usize)).and_then(|d| d.as_string()).map(|s| s.trim().to_string());
         let delivery_time_item = DeliveryTime {
             material_name:  range.get((row, 0usize)).unwrap().as_string().unwrap(),
-            weeks:          range.get((row, 1usize)).unwrap().as_i64().unwrap() as u32,
+            qty:            range.get((row, 1usize)).unwrap().as_i64().unwrap() as u32,
+            unit:           LeadTimeUnit::parse(&unit).unwrap_or_else(|e| panic!("{} в файле: Сроки доставки.xlsx", e)),
+            supplier:       supplier.filter(|s| !s.is_empty()),
+            safety_stock:   range.get((row, 4usize)).and_then(read_decimal),
         };
         delivery_times.push(delivery_time_item);
     }, |range|{
         (0,range.height(),0,1)
     });
-    return delivery_times
+    delivery_times
+}
+
+/// Приоритеты товаров для распределения материалов. Колонки: товар, приоритет (1 - высший)
+pub fn read_priorities(settings: &Settings) -> Result<Vec<(String, u32)>, String> {
+    let mut priorities = vec![];
+    let (path, sheet) = get_source(settings, "Приоритеты", "Приоритеты");
+    if !Path::new(&path).exists() {
+        return Err(format!("Не найден файл приоритетов товаров для распределения (-allocation): {}", path));
+    }
+    if let (Some(sheet), Some(_)) = (&sheet, &settings.workbook) {
+        let has_sheet = open_workbook_auto(&path).map(|wb| wb.sheet_names().contains(sheet)).unwrap_or(false);
+        if !has_sheet {
+            return Err(format!("В файле {} нет листа \"{}\" для распределения (-allocation)", path, sheet));
+        }
+    }
+    read_table(path, sheet.as_deref(), |row, _, range| {
+        let product = range.get((row, 0usize)).and_then(|d| d.as_string());
+        let priority = range.get((row, 1usize)).and_then(|d| d.as_i64());
+        if let (Some(product), Some(priority)) = (product, priority) {
+            priorities.push((product.trim().to_string(), priority.max(0) as u32));
+        }
+    }, |range| {
+        (0, range.height(), 0, 1)
+    });
+    Ok(priorities)
+}
+
+/// Подтвержденные поступления по заказам поставщикам, необязательный файл "Поступления"
+/// (в сводной книге - лист "Поступления"). Колонки: заказ, материал, получено (количество или "отменен");
+/// без материала - отмена всего заказа
+pub fn read_receipts(settings: &Settings) -> Vec<Receipt> {
+    let mut receipts = vec![];
+    let (path, sheet) = get_source(settings, "Поступления", "Поступления");
+    if !Path::new(&path).exists() {
+        return receipts;
+    }
+    if let (Some(sheet), Some(_)) = (&sheet, &settings.workbook) {
+        let has_sheet = open_workbook_auto(&path).map(|wb| wb.sheet_names().contains(sheet)).unwrap_or(false);
+        if !has_sheet {
+            return receipts;
+        }
+    }
+    read_table(path, sheet.as_deref(), |row, _, range| {
+        let Some(order) = range.get((row, 0usize)).and_then(|d| d.as_string()).map(|o| o.trim().to_string()).filter(|o| !o.is_empty()) else {
+            return;
+        };
+        let material = range.get((row, 1usize)).and_then(|d| d.as_string()).map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+        let value = range.get((row, 2usize));
+        let received = match value.and_then(read_decimal) {
+            Some(qty) => Some(qty),
+            None if value.and_then(|d| d.as_string()).is_some_and(|v| v.trim().to_lowercase().starts_with("отмен")) => None,
+            None => return
+        };
+        if material.is_none() && received.is_some() {
+            return;
+        }
+        receipts.push(Receipt { order, material, received });
+    }, |range| {
+        (0, range.height(), 0, 1)
+    });
+    receipts
+}
+
+/// Утвержденные предложения к заказу, файл "Предложения" (в сводной книге - лист "Предложения").
+/// Колонки: материал, дата поступления, количество, утверждено ("да"); первая строка - заголовок.
+/// Дата приводится к понедельнику: файлы заказов всегда по неделям, независимо от -period
+pub fn read_approved_proposals(settings: &Settings) -> Result<Vec<MaterialInfo>, String> {
+    let mut proposals = vec![];
+    let (path, sheet) = get_source(settings, "Предложения", "Предложения");
+    if !Path::new(&path).exists() {
+        return Err(format!("Не найден файл предложений к заказу: {}", path));
+    }
+    read_table(path, sheet.as_deref(), |row, _, range| {
+        let approved = range.get((row, 3usize)).and_then(|d| d.as_string()).is_some_and(|v| v.trim().to_lowercase() == "да");
+        let material = range.get((row, 0usize)).and_then(|d| d.as_string()).map(|m| m.trim().to_string());
+        let date = range.get((row, 1usize)).and_then(|d| d.as_date());
+        let qty = range.get((row, 2usize)).and_then(read_decimal).filter(|qty| *qty > Decimal::ZERO);
+        if let (true, Some(material), Some(date), Some(qty)) = (approved, material, date, qty) {
+            proposals.push(MaterialInfo { material, date: Period::Week.start(&date), qty });
+        }
+    }, |range| {
+        (1, range.height(), 0, 1)
+    });
+    Ok(proposals)
+}
+
+/// Файлы каталога "Календари": "Общий.xlsx" - нерабочие дни компании,
+/// "<Поставщик>.xlsx" - нерабочие дни поставщика.
+/// Колонки: начало периода, окончание периода (если пусто - один день).
+pub fn read_calendars() -> Vec<Calendar> {
+    let mut result = vec![];
+    let dir = get_template_path("Календари");
+    if !Path::new(&dir).exists() {
+        return result;
+    }
+    for entry in fs::read_dir(dir).unwrap() {
+        let dir_e = entry.unwrap();
+        let path = dir_e.path().into_os_string().to_str().unwrap().to_string();
+        let file_name = dir_e.file_name().to_str().unwrap().to_string();
+        if !is_input_file(&path) {
+            continue;
+        }
+        let name = file_name.split('.').collect::<Vec<&str>>()[0].to_string();
+        let mut calendar = Calendar {
+            supplier: if name == "Общий" { None } else { Some(name) },
+            holidays: vec![],
+        };
+        read_table(path, None, |row, _, range| {
+            if let Some(from) = range.get((row, 0usize)).and_then(|d| d.as_date()) {
+                let to = range.get((row, 1usize)).and_then(|d| d.as_date()).unwrap_or(from);
+                calendar.holidays.push((from, to));
+            }
+        }, |range| {
+            (0, range.height(), 0, 1)
+        });
+        result.push(calendar);
+    }
+    result
+}
+
+/// Файлы каталога "Сценарии": имя файла - название сценария.
+/// Колонки: параметр, объект, значение; первая строка - заголовок.
+/// Параметры: "план" (объект - дополнительный файл плана в каталоге исходных данных),
+/// "множитель" (товар), "задержка заказа" (номер заказа, недели),
+/// "задержка поставщика" (поставщик, недели), "срок доставки" (материал, срок в единицах из файла сроков).
+pub fn read_scenarios() -> Vec<Scenario> {
+    let mut result = vec![];
+    let dir = get_template_path("Сценарии");
+    if !Path::new(&dir).exists() {
+        return result;
+    }
+    let mut paths: Vec<String> = fs::read_dir(dir).unwrap()
+        .map(|e| e.unwrap().path().into_os_string().to_str().unwrap().to_string())
+        .filter(|path| is_input_file(path))
+        .collect();
+    paths.sort();
+    for path in paths {
+        let file_name = Path::new(&path).file_name().unwrap().to_string_lossy().to_string();
+        let mut scenario = Scenario::new(file_name.split('.').next().unwrap());
+        read_table(path, None, |row, _, range| {
+            let parameter = range.get((row, 0usize)).and_then(|d| d.as_string()).unwrap_or_default();
+            let object = range.get((row, 1usize)).and_then(|d| d.as_string()).map(|s| s.trim().to_string()).unwrap_or_default();
+            let value = range.get((row, 2usize)).and_then(read_decimal);
+            if parameter.trim().is_empty() {
+                return;
+            }
+            scenario.add_parameter(&parameter, object, value)
+                .unwrap_or_else(|e| panic!("{} в файле {}, строка {}", e, file_name, row + 1));
+        }, |range| {
+            (1, range.height(), 0, 1)
+        });
+        result.push(scenario);
+    }
+    result
 }
 
 pub fn read_decimal(data: &Data) -> Option<Decimal> {
@@ -87,23 +366,18 @@ pub fn read_decimal(data: &Data) -> Option<Decimal> {
     }
 }
 
-pub fn read_stocks() -> Vec<MaterialInfo> {
+pub fn read_stocks(settings: &Settings) -> Vec<MaterialInfo> {
     let mut stocks = vec![];
-    read_xlsx(get_template_path("Остатки.xlsx"), |row, col, range|{
-        let qty_opt = range.get((row, col));
-        if qty_opt.is_some() {
-            let dec = read_decimal(qty_opt.unwrap());
-
-            if dec.is_some() {
-                let mi = MaterialInfo{
-                    date: range.get((0usize, col)).unwrap().as_date().unwrap(),
-                    material: range.get((row, 0usize)).unwrap().as_string().unwrap(),
-                    qty: dec.unwrap()
-                };
-                check_date(&mi.date,&"Остатки.xlsx".to_string());
-                stocks.push(mi);
-
-            }
+    let (path, sheet) = get_source(settings, "Остатки", "Остатки");
+    read_table(path, sheet.as_deref(), |row, col, range|{
+        if let Some(qty) = range.get((row, col)).and_then(read_decimal) {
+            let date = range.get((0usize, col)).unwrap().as_date().unwrap();
+            let mi = MaterialInfo{
+                date: settings.period.check_date(&date, "Остатки.xlsx"),
+                material: range.get((row, 0usize)).unwrap().as_string().unwrap(),
+                qty
+            };
+            stocks.push(mi);
         }
     }, |range|{
         (1, range.height(), 1, range.width())
@@ -111,26 +385,53 @@ pub fn read_stocks() -> Vec<MaterialInfo> {
     stocks
 }
 
-pub fn read_specifications() -> Vec<Specification> {
+/// Спецификации на листе "Спецификации" сводной книги.
+/// Колонки: изделие, дата начала действия, материал, количество; первая строка - заголовок.
+fn read_specifications_table(path: String) -> Vec<Specification> {
+    let mut result: Vec<Specification> = vec![];
+    read_table(path, Some("Спецификации"), |row, _, range| {
+        let product_name = range.get((row, 0usize)).unwrap().as_string().unwrap();
+        let date_from = range.get((row, 1usize)).and_then(|d| d.as_date()).unwrap_or(NaiveDate::MIN);
+        let sp_item = SpecificationItem {
+            material_name: range.get((row, 2usize)).unwrap().as_string().unwrap(),
+            qty: read_decimal(range.get((row, 3usize)).unwrap()).unwrap(),
+        };
+        match result.iter_mut().find(|sp| sp.product_name == product_name && sp.date_from == date_from) {
+            Some(sp) => sp.items.push(sp_item),
+            None => result.push(Specification { product_name, date_from, items: vec![sp_item] })
+        }
+    }, |range| {
+        (1, range.height(), 0, 1)
+    });
+    result
+}
+
+pub fn read_specifications(settings: &Settings) -> Vec<Specification> {
+    if let Some(workbook) = &settings.workbook {
+        return read_specifications_table(get_template_path(workbook));
+    }
     let mut result = vec![];
 
     for entry in fs::read_dir(get_template_path("Спецификации")).unwrap() {
         let dir_e = entry.unwrap();
         let path = dir_e.path().into_os_string().to_str().unwrap().to_string();
         let file_name = dir_e.file_name().to_str().unwrap().to_string();
-        let name_parts = file_name.split(".").collect::<Vec<&str>>()[0].split("_").collect::<Vec<&str>>();
+        if !is_input_file(&path) {
+            continue;
+        }
+        let name_parts = file_name.split('.').collect::<Vec<&str>>()[0].split('_').collect::<Vec<&str>>();
         if name_parts.len() != 2 {
             panic!("{} invalid file name", path)
         }
         let name = name_parts[0].to_string();
-        let date = NaiveDate::parse_from_str(name_parts[1],"%Y%m%d").expect(&format!("Не удалось извлечь дату из файла спецификации ({})", file_name));
+        let date = NaiveDate::parse_from_str(name_parts[1],"%Y%m%d").unwrap_or_else(|_| panic!("Не удалось извлечь дату из файла спецификации ({})", file_name));
         let mut sp = Specification{
             product_name: name,
             date_from: date,
             items: vec![],
         };
 
-        read_xlsx(path.to_string(), |row, _, range|{
+        read_table(path.to_string(), settings.sheet.as_deref(), |row, _, range|{
             let sp_item = SpecificationItem{
                 material_name: range.get((row,0usize)).unwrap().as_string().unwrap(),
                 qty: read_decimal(range.get((row, 1usize)).unwrap()).unwrap(),
@@ -144,13 +445,47 @@ pub fn read_specifications() -> Vec<Specification> {
     result
 }
 
-pub fn read_purchase_orders() -> Vec<PurchaseOrder> {
+/// Заказы на листе "Заказы" сводной книги.
+/// Колонки: номер заказа, материал, дата поступления, количество, дата заказа (необязательно); первая строка - заголовок.
+fn read_purchase_orders_table(path: String, settings: &Settings) -> Vec<PurchaseOrder> {
+    let mut result: Vec<PurchaseOrder> = vec![];
+    read_table(path, Some("Заказы"), |row, _, range| {
+        let Some(qty) = range.get((row, 3usize)).and_then(read_decimal) else {
+            return;
+        };
+        let name = range.get((row, 0usize)).unwrap().as_string().unwrap();
+        let date = range.get((row, 2usize)).unwrap().as_date().unwrap();
+        let order_date = range.get((row, 4usize)).and_then(|d| d.as_date());
+        let po_item = MaterialInfo {
+            material: range.get((row, 1usize)).unwrap().as_string().unwrap(),
+            date: settings.period.check_date(&date, "Заказы"),
+            qty,
+        };
+        match result.iter_mut().find(|po| po.name == name) {
+            Some(po) => po.items.push(po_item),
+            None => result.push(PurchaseOrder { name, items: vec![po_item], status: OrderStatus::Open, order_date })
+        }
+    }, |range| {
+        (1, range.height(), 0, 1)
+    });
+    result
+}
+
+/// Файлы "Заказ_<номер>" каталога "Заказы поставщикам": строка дат поступления, две строки заголовка
+/// ("Поставщик:", "Дата заказа:" и значение в следующей колонке), далее материал и количества по датам
+pub fn read_purchase_orders(settings: &Settings) -> Vec<PurchaseOrder> {
+    if let Some(workbook) = &settings.workbook {
+        return read_purchase_orders_table(get_template_path(workbook), settings);
+    }
     let mut result = vec![];
     for dir_e in fs::read_dir(get_template_path("Заказы поставщикам")).unwrap() {
         let dir_e = dir_e.unwrap();
         let path = dir_e.path().into_os_string().to_str().unwrap().to_string();
         let file_name = dir_e.file_name().to_str().unwrap().to_string();
-        let name_parts = file_name.split(".").collect::<Vec<&str>>()[0].split("_").collect::<Vec<&str>>();
+        if !is_input_file(&path) {
+            continue;
+        }
+        let name_parts = file_name.split('.').collect::<Vec<&str>>()[0].split('_').collect::<Vec<&str>>();
         if name_parts.len() != 2 {
             panic!("{} invalid file name", path)
         }
@@ -158,16 +493,25 @@ pub fn read_purchase_orders() -> Vec<PurchaseOrder> {
         let mut po = PurchaseOrder {
             name,
             items: vec![],
+            status: OrderStatus::Open,
+            order_date: None,
         };
-        read_xlsx(path.to_string(), |row, col, range| {
-            let qty_o = read_decimal(range.get((row, col)).unwrap());
-            if qty_o.is_some() {
+        read_table(path.to_string(), settings.sheet.as_deref(), |row, _, range| {
+            let label = range.get((row, 0usize)).and_then(|d| d.as_string()).unwrap_or_default();
+            if label.trim().to_lowercase().starts_with("дата заказа") {
+                po.order_date = range.get((row, 1usize)).and_then(|d| d.as_date());
+            }
+        }, |range| {
+            (1, range.height().min(3), 0, 1)
+        });
+        read_table(path.to_string(), settings.sheet.as_deref(), |row, col, range| {
+            if let Some(qty) = read_decimal(range.get((row, col)).unwrap()) {
+                let date = range.get((0usize, col)).unwrap().as_date().unwrap();
                 let po_item = MaterialInfo {
                     material: range.get((row, 0usize)).unwrap().as_string().unwrap(),
-                    date: range.get((0usize, col)).unwrap().as_date().unwrap(),
-                    qty: qty_o.unwrap(),
+                    date: settings.period.check_date(&date, &file_name),
+                    qty,
                 };
-                check_date(&po_item.date,&file_name);
                 po.items.push(po_item);
             }
         }, |range| {